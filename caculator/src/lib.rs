@@ -0,0 +1,149 @@
+//! 简单计算器：支持 `+ - * /` 和括号的四则运算。
+
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug)]
+pub enum Token {
+    Number(f64),
+    Plus,
+    Minus,
+    Multiply,
+    Divide,
+    LeftParen,
+    RightParen,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalcError {
+    UnexpectedCharacter(char),
+    InvalidNumber(String),
+    MismatchedParen,
+    MissingOperand,
+    Empty,
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalcError::UnexpectedCharacter(c) => write!(f, "unexpected character: {}", c),
+            CalcError::InvalidNumber(number) => write!(f, "invalid number: {}", number),
+            CalcError::MismatchedParen => write!(f, "mismatched parenthesis"),
+            CalcError::MissingOperand => write!(f, "missing operand"),
+            CalcError::Empty => write!(f, "empty expression"),
+        }
+    }
+}
+
+impl std::error::Error for CalcError {}
+
+fn parse_number(chars: &mut Peekable<Chars>) -> Result<f64, CalcError> {
+    let mut number = String::new();
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            chars.next(); // consume
+        } else {
+            break;
+        }
+    }
+    number.parse().map_err(|_| CalcError::InvalidNumber(number))
+}
+
+pub fn tokenize(input: &str) -> Result<Vec<Token>, CalcError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        let token = match c {
+            '0'..='9' | '.' => {
+                tokens.push(Token::Number(parse_number(&mut chars)?));
+                continue;
+            }
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Multiply,
+            '/' => Token::Divide,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            _ => return Err(CalcError::UnexpectedCharacter(c)),
+        };
+        chars.next();
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn precedence(token: &Token) -> u8 {
+    // 在 `match` 表达式中，对引用进行模式匹配是很常见的做法，Rust 会自动解引用。
+    match token {
+        Token::Plus | Token::Minus => 1,
+        Token::Multiply | Token::Divide => 2,
+        _ => 0,
+    }
+}
+
+fn apply_operator(numbers: &mut Vec<f64>, op: &Token) -> Result<(), CalcError> {
+    let b = numbers.pop().ok_or(CalcError::MissingOperand)?;
+    let a = numbers.pop().ok_or(CalcError::MissingOperand)?;
+    let result = match op {
+        Token::Plus => a + b,
+        Token::Minus => a - b,
+        Token::Divide => a / b,
+        Token::Multiply => a * b,
+        _ => return Err(CalcError::MismatchedParen),
+    };
+    numbers.push(result);
+    Ok(())
+}
+
+pub fn evaluate(tokens: &[Token]) -> Result<f64, CalcError> {
+    let mut numbers = Vec::new();
+    let mut operators: Vec<&Token> = Vec::new();
+
+    for token in tokens {
+        match token {
+            Token::Number(num) => numbers.push(*num),
+            Token::Plus | Token::Minus | Token::Divide | Token::Multiply => {
+                while let Some(op) = operators.last() {
+                    if precedence(op) >= precedence(token) {
+                        apply_operator(&mut numbers, operators.pop().unwrap())?;
+                    } else {
+                        break;
+                    }
+                }
+                operators.push(token);
+            }
+            Token::LeftParen => operators.push(token),
+            Token::RightParen => loop {
+                match operators.pop() {
+                    Some(Token::LeftParen) => break,
+                    Some(op) => apply_operator(&mut numbers, op)?,
+                    None => return Err(CalcError::MismatchedParen),
+                }
+            },
+        }
+    }
+    while let Some(op) = operators.pop() {
+        // 还留在栈上的左括号没有闭合
+        if let Token::LeftParen = op {
+            return Err(CalcError::MismatchedParen);
+        }
+        apply_operator(&mut numbers, op)?;
+    }
+    match numbers.as_slice() {
+        [] => Err(CalcError::Empty),
+        [result] => Ok(*result),
+        _ => Err(CalcError::MissingOperand),
+    }
+}
+
+/// Tokenizes and evaluates `input` in one step.
+pub fn eval(input: &str) -> Result<f64, CalcError> {
+    evaluate(&tokenize(input)?)
+}
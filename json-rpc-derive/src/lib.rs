@@ -0,0 +1,356 @@
+//! `#[rpc]`：把一个 Rust trait 暴露成 JSON-RPC 服务。
+//!
+//! ```ignore
+//! #[rpc]
+//! trait Calculator {
+//!     /// Adds two numbers.
+//!     fn add(&self, a: f64, b: f64) -> f64;
+//!     #[rpc(name = "calc.div")]
+//!     fn div(&self, a: f64, b: f64) -> Result<f64, RpcError>;
+//! }
+//! ```
+//!
+//! generates, next to the trait:
+//!
+//! - a provided `register_rpc(self, &mut Router)` method that registers every
+//!   trait method, with params accepted by name (`{"a": 1, "b": 2}`) or by
+//!   position (`[1, 2]`) and doc comments used as `rpc.discover` descriptions;
+//! - a `CalculatorClient` stub wrapping a `json_rpc::Client`, with one typed
+//!   method per trait method.
+//!
+//! Methods take `&self` and owned arguments. A method returning
+//! `Result<T, RpcError>` passes errors through; any other return type is
+//! always a success. A `Result` with another error type, such as
+//! `io::Result<T>`, is a compile error.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Attribute, Error, FnArg, Ident, ItemTrait, LitStr, Pat, PathArguments,
+    ReturnType, TraitItem, TraitItemFn, Type,
+};
+
+struct Method {
+    ident: Ident,
+    rpc_name: String,
+    description: Option<String>,
+    args: Vec<(Ident, Type)>,
+    output: Type,
+    /// The `T` in `Result<T, RpcError>`, when the method is fallible.
+    ok: Option<Type>,
+}
+
+#[proc_macro_attribute]
+pub fn rpc(args: TokenStream, input: TokenStream) -> TokenStream {
+    if !args.is_empty() {
+        let args = TokenStream2::from(args);
+        return Error::new(args.span(), "#[rpc] on a trait takes no arguments")
+            .to_compile_error()
+            .into();
+    }
+    let item = parse_macro_input!(input as ItemTrait);
+    expand(item)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(mut item: ItemTrait) -> syn::Result<TokenStream2> {
+    let mut methods = Vec::new();
+    for trait_item in &mut item.items {
+        if let TraitItem::Fn(function) = trait_item {
+            methods.push(parse_method(function)?);
+        }
+    }
+
+    let vis = &item.vis;
+    let trait_ident = &item.ident;
+    let module = format_ident!("__{}_rpc", to_snake_case(&trait_ident.to_string()));
+    let client = format_ident!("{}Client", trait_ident);
+
+    let params_structs = methods.iter().filter(|m| !m.args.is_empty()).map(|method| {
+        let name = params_ident(method);
+        let fields = method.args.iter().map(|(ident, ty)| quote!(pub #ident: #ty));
+        quote! {
+            #[derive(::json_rpc::serde::Serialize, ::json_rpc::serde::Deserialize, ::json_rpc::schemars::JsonSchema)]
+            #[serde(crate = "::json_rpc::serde")]
+            #[schemars(crate = "::json_rpc::schemars")]
+            pub struct #name { #(#fields,)* }
+        }
+    });
+
+    let registrations = methods.iter().map(|method| {
+        let ident = &method.ident;
+        let rpc_name = &method.rpc_name;
+        let arg_idents: Vec<&Ident> = method.args.iter().map(|(ident, _)| ident).collect();
+        let (params_ty, destructure) = if method.args.is_empty() {
+            (quote!(()), quote!(()))
+        } else {
+            let name = params_ident(method);
+            (
+                quote!(#module::#name),
+                quote!(#module::#name { #(#arg_idents),* }),
+            )
+        };
+        let call = quote!(__service.#ident(#(#arg_idents),*));
+        let body = if method.ok.is_some() {
+            call
+        } else {
+            quote!(::std::result::Result::Ok::<_, ::json_rpc::RpcError>(#call))
+        };
+        let describe = method
+            .description
+            .as_ref()
+            .map(|description| quote!(__router.describe(#rpc_name, #description);));
+        // 生成的局部变量带上前缀，不会和方法参数同名
+        quote! {
+            {
+                let __service = ::std::sync::Arc::clone(&__service);
+                __router.register(#rpc_name, move |#destructure: #params_ty| #body);
+                #describe
+            }
+        }
+    });
+
+    let stubs = methods.iter().map(|method| {
+        let ident = &method.ident;
+        let rpc_name = &method.rpc_name;
+        let args = method.args.iter().map(|(ident, ty)| quote!(#ident: #ty));
+        let arg_idents: Vec<&Ident> = method.args.iter().map(|(ident, _)| ident).collect();
+        let params = if method.args.is_empty() {
+            quote!(())
+        } else {
+            let name = params_ident(method);
+            quote!(#module::#name { #(#arg_idents),* })
+        };
+        let ok = method.ok.as_ref().unwrap_or(&method.output);
+        let doc = format!("Calls `{}` on the server.", rpc_name);
+        quote! {
+            #[doc = #doc]
+            pub fn #ident(&self #(, #args)*) -> ::std::result::Result<#ok, ::json_rpc::RpcError> {
+                self.client.call(#rpc_name, #params)
+            }
+        }
+    });
+
+    item.items.push(syn::parse_quote! {
+        /// Registers every method of this trait on `router`.
+        fn register_rpc(self, __router: &mut ::json_rpc::Router)
+        where
+            Self: Sized + Send + Sync + 'static,
+        {
+            let __service = ::std::sync::Arc::new(self);
+            #(#registrations)*
+        }
+    });
+
+    let client_doc = format!("Typed client for the [`{}`] service.", trait_ident);
+    Ok(quote! {
+        #item
+
+        #[doc(hidden)]
+        #[allow(non_snake_case, non_camel_case_types)]
+        #vis mod #module {
+            #[allow(unused_imports)]
+            use super::*;
+            #(#params_structs)*
+        }
+
+        #[doc = #client_doc]
+        #vis struct #client {
+            client: ::json_rpc::Client,
+        }
+
+        impl #client {
+            pub fn new(client: ::json_rpc::Client) -> Self {
+                #client { client }
+            }
+
+            pub fn client(&self) -> &::json_rpc::Client {
+                &self.client
+            }
+
+            pub fn into_inner(self) -> ::json_rpc::Client {
+                self.client
+            }
+
+            #(#stubs)*
+        }
+    })
+}
+
+fn parse_method(function: &mut TraitItemFn) -> syn::Result<Method> {
+    let sig = &function.sig;
+    if sig.generics.lt_token.is_some() || sig.asyncness.is_some() {
+        return Err(Error::new(
+            sig.span(),
+            "#[rpc] methods cannot be generic or async",
+        ));
+    }
+    let mut inputs = sig.inputs.iter();
+    match inputs.next() {
+        Some(FnArg::Receiver(receiver))
+            if receiver.reference.is_some() && receiver.mutability.is_none() => {}
+        _ => return Err(Error::new(sig.span(), "#[rpc] methods must take `&self`")),
+    }
+    let mut args = Vec::new();
+    for input in inputs {
+        let FnArg::Typed(arg) = input else {
+            unreachable!("receiver can only come first");
+        };
+        let Pat::Ident(pat) = &*arg.pat else {
+            return Err(Error::new(
+                arg.pat.span(),
+                "#[rpc] arguments must be plain identifiers",
+            ));
+        };
+        if let Type::Reference(ty) = &*arg.ty {
+            return Err(Error::new(
+                ty.span(),
+                "#[rpc] arguments must be owned types",
+            ));
+        }
+        args.push((pat.ident.clone(), (*arg.ty).clone()));
+    }
+    let output = match &sig.output {
+        ReturnType::Default => syn::parse_quote!(()),
+        ReturnType::Type(_, ty) => (**ty).clone(),
+    };
+    let ok = result_ok_type(&output)?;
+
+    let ident = sig.ident.clone();
+    let rpc_name = take_rpc_name(&mut function.attrs)?.unwrap_or_else(|| ident.to_string());
+    Ok(Method {
+        ident,
+        rpc_name,
+        description: doc_comment(&function.attrs),
+        args,
+        output,
+        ok,
+    })
+}
+
+/// Removes `#[rpc(name = "...")]` from a method and returns the name.
+fn take_rpc_name(attrs: &mut Vec<Attribute>) -> syn::Result<Option<String>> {
+    let mut name = None;
+    let mut error = None;
+    attrs.retain(|attr| {
+        if !attr.path().is_ident("rpc") {
+            return true;
+        }
+        let parsed = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else {
+                Err(meta.error("expected `name = \"...\"`"))
+            }
+        });
+        if let Err(err) = parsed {
+            error = Some(err);
+        }
+        false
+    });
+    match error {
+        Some(err) => Err(err),
+        None => Ok(name),
+    }
+}
+
+fn doc_comment(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(meta) => match &meta.value {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(doc),
+                    ..
+                }) => Some(doc.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    let doc = lines.join("\n").trim().to_string();
+    (!doc.is_empty()).then_some(doc)
+}
+
+/// Returns `T` when `ty` is written as `Result<T, RpcError>`. Any other
+/// `Result` is an error: only an `RpcError` can be sent back to the caller.
+fn result_ok_type(ty: &Type) -> syn::Result<Option<Type>> {
+    let Type::Path(path) = ty else {
+        return Ok(None);
+    };
+    let Some(segment) = path.path.segments.last() else {
+        return Ok(None);
+    };
+    if segment.ident != "Result" {
+        return Ok(None);
+    }
+    let unsupported = || {
+        Error::new_spanned(
+            ty,
+            "#[rpc] methods that fail must return `Result<T, RpcError>`",
+        )
+    };
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return Err(unsupported());
+    };
+    let mut args = args.args.iter();
+    match (args.next(), args.next(), args.next()) {
+        (
+            Some(syn::GenericArgument::Type(ok)),
+            Some(syn::GenericArgument::Type(Type::Path(error))),
+            None,
+        ) if error
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "RpcError") =>
+        {
+            Ok(Some(ok.clone()))
+        }
+        // `io::Result<T>`、`Result<T, String>` 之类的错误类型没法回给调用方
+        (_, Some(error), _) => Err(Error::new_spanned(
+            error,
+            "#[rpc] methods that fail must return `Result<T, RpcError>`",
+        )),
+        _ => Err(unsupported()),
+    }
+}
+
+fn params_ident(method: &Method) -> Ident {
+    Ident::new(
+        &format!("{}Params", to_camel_case(&method.ident.to_string())),
+        Span::call_site(),
+    )
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (index, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if index > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+fn to_camel_case(name: &str) -> String {
+    name.split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
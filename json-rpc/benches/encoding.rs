@@ -0,0 +1,113 @@
+//! 比较 JSON、MessagePack 和 CBOR：先单独测编解码，再测经过 TCP 的完整调用。
+//!
+//! Run with `cargo bench -p json-rpc --bench encoding`.
+
+use json_rpc::{transport, Client, Encoding, Framing, Router, RpcError, Server};
+use serde_json::{json, Value};
+use std::hint::black_box;
+use std::net::TcpListener;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const ENCODINGS: [Encoding; 3] = [Encoding::Json, Encoding::MessagePack, Encoding::Cbor];
+
+fn small() -> Value {
+    json!({"jsonrpc": "2.0", "id": 1, "method": "add", "params": [1, 2]})
+}
+
+fn large() -> Value {
+    let rows: Vec<Value> = (0..200)
+        .map(|i| {
+            json!({
+                "id": i,
+                "name": format!("item-{}", i),
+                "price": i as f64 * 1.25,
+                "tags": ["alpha", "beta", "gamma"],
+                "active": i % 2 == 0,
+            })
+        })
+        .collect();
+    json!({"jsonrpc": "2.0", "id": 1, "method": "echo", "params": {"rows": rows}})
+}
+
+/// Runs `f` repeatedly for about `budget` and returns iterations per second.
+fn rate(budget: Duration, mut f: impl FnMut()) -> (u64, f64) {
+    let started = Instant::now();
+    let mut iterations = 0;
+    while started.elapsed() < budget {
+        f();
+        iterations += 1;
+    }
+    (
+        iterations,
+        iterations as f64 / started.elapsed().as_secs_f64(),
+    )
+}
+
+fn codec(name: &str, message: &Value) {
+    println!("codec, {} message", name);
+    println!(
+        "{:<10} {:>8} {:>14} {:>10}",
+        "encoding", "bytes", "round trips/s", "MB/s"
+    );
+    for encoding in ENCODINGS {
+        let size = encoding.encode(message).unwrap().len();
+        let (_, per_second) = rate(Duration::from_millis(500), || {
+            let bytes = encoding.encode(black_box(message)).unwrap();
+            black_box(encoding.decode(&bytes).unwrap());
+        });
+        println!(
+            "{:<10} {:>8} {:>14.0} {:>10.1}",
+            encoding,
+            size,
+            per_second,
+            per_second * size as f64 / 1e6
+        );
+    }
+    println!();
+}
+
+fn end_to_end(name: &str, params: &Value) {
+    let mut router = Router::new();
+    router.register("echo", |params: Value| Ok::<_, RpcError>(params));
+    let server = Server::new(router);
+
+    let newline = TcpListener::bind("127.0.0.1:0").unwrap();
+    let content_length = TcpListener::bind("127.0.0.1:0").unwrap();
+    let newline_addr = newline.local_addr().unwrap();
+    let content_length_addr = content_length.local_addr().unwrap();
+    {
+        let server = server.clone();
+        thread::spawn(move || transport::tcp::serve(newline, server, Framing::Newline));
+    }
+    thread::spawn(move || transport::tcp::serve(content_length, server, Framing::ContentLength));
+
+    println!("tcp echo, {} params", name);
+    println!("{:<28} {:>10}", "protocol", "calls/s");
+    let baseline = Client::connect_tcp(newline_addr, Framing::Newline).unwrap();
+    let (_, per_second) = rate(Duration::from_secs(1), || {
+        black_box(baseline.call::<_, Value>("echo", params).unwrap());
+    });
+    println!("{:<28} {:>10.0}", "newline + json (baseline)", per_second);
+    for encoding in ENCODINGS {
+        let client = Client::connect_tcp(content_length_addr, Framing::ContentLength)
+            .unwrap()
+            .with_encoding(encoding);
+        let (_, per_second) = rate(Duration::from_secs(1), || {
+            black_box(client.call::<_, Value>("echo", params).unwrap());
+        });
+        println!(
+            "{:<28} {:>10.0}",
+            format!("content-length + {}", encoding),
+            per_second
+        );
+    }
+    println!();
+}
+
+fn main() {
+    codec("small", &small());
+    codec("large", &large());
+    end_to_end("small", &small()["params"]);
+    end_to_end("large", &large()["params"]);
+}
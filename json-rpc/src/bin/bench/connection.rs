@@ -0,0 +1,170 @@
+//! 压测客户端的连接：TCP、Unix 和 stdio 复用库里的 `Client`，HTTP 和 WebSocket 手写最小实现。
+
+use json_rpc::transport::http::PATH;
+use json_rpc::{Client, Framing, Request, Response, RpcError};
+use serde_json::Value;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::Path;
+use std::process::Command;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+pub enum Connection {
+    Rpc(Client),
+    Http {
+        addr: SocketAddr,
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+        next_id: u64,
+    },
+    WebSocket {
+        socket: WebSocket<MaybeTlsStream<TcpStream>>,
+        next_id: u64,
+    },
+}
+
+impl Connection {
+    pub fn tcp(addr: SocketAddr, framing: Framing) -> io::Result<Self> {
+        Ok(Connection::Rpc(Client::connect_tcp(addr, framing)?))
+    }
+
+    #[cfg(unix)]
+    pub fn unix(path: &Path, framing: Framing) -> io::Result<Self> {
+        Ok(Connection::Rpc(Client::connect_unix(path, framing)?))
+    }
+
+    #[cfg(not(unix))]
+    pub fn unix(_path: &Path, _framing: Framing) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Unix sockets are not supported",
+        ))
+    }
+
+    /// Spawns a server of its own and talks to it over stdin/stdout.
+    pub fn stdio(command: &mut Command, framing: Framing) -> io::Result<Self> {
+        Ok(Connection::Rpc(Client::spawn(command, framing)?))
+    }
+
+    pub fn http(addr: SocketAddr) -> io::Result<Self> {
+        let writer = TcpStream::connect(addr)?;
+        writer.set_nodelay(true)?;
+        let reader = BufReader::new(writer.try_clone()?);
+        Ok(Connection::Http {
+            addr,
+            reader,
+            writer,
+            next_id: 1,
+        })
+    }
+
+    pub fn websocket(addr: SocketAddr) -> io::Result<Self> {
+        let (socket, _) =
+            tungstenite::connect(format!("ws://{}", addr)).map_err(io::Error::other)?;
+        Ok(Connection::WebSocket { socket, next_id: 1 })
+    }
+
+    pub fn call(&mut self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        match self {
+            Connection::Rpc(client) => client.call(method, params),
+            Connection::Http {
+                addr,
+                reader,
+                writer,
+                next_id,
+            } => {
+                let body = request(method, params, next_id);
+                write!(
+                    writer,
+                    "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\n\r\n",
+                    PATH,
+                    addr,
+                    body.len()
+                )
+                .and_then(|()| writer.write_all(&body))
+                .map_err(RpcError::transport)?;
+                let body = read_response(reader).map_err(RpcError::transport)?;
+                decode(&body)
+            }
+            Connection::WebSocket { socket, next_id } => {
+                let id = *next_id;
+                let body = request(method, params, next_id);
+                let text = String::from_utf8(body).expect("JSON is UTF-8");
+                socket
+                    .send(Message::text(text))
+                    .map_err(RpcError::transport)?;
+                loop {
+                    // 跳过 `$/progress` 之类的通知
+                    let message = socket.read().map_err(RpcError::transport)?;
+                    if let Message::Text(text) = message {
+                        let value: Value =
+                            serde_json::from_str(&text).map_err(RpcError::transport)?;
+                        if value["id"] == id {
+                            return decode(text.as_bytes());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Runs the `shutdown`/`exit` handshake so a spawned server exits cleanly.
+    pub fn close(self) -> io::Result<()> {
+        match self {
+            Connection::Rpc(client) => {
+                client.shutdown().map_err(io::Error::other)?;
+            }
+            Connection::Http { .. } => {}
+            Connection::WebSocket { mut socket, .. } => {
+                let _ = socket.close(None);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn request(method: &str, params: &Value, next_id: &mut u64) -> Vec<u8> {
+    let request = Request::new(method, Some(params.clone()), Some(Value::from(*next_id)));
+    *next_id += 1;
+    serde_json::to_vec(&request).expect("requests always serialize")
+}
+
+fn decode(body: &[u8]) -> Result<Value, RpcError> {
+    let response: Response = serde_json::from_slice(body).map_err(RpcError::transport)?;
+    response.into_result()
+}
+
+/// Reads one HTTP response and returns its body.
+fn read_response(reader: &mut BufReader<TcpStream>) -> io::Result<Vec<u8>> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let status = line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or_default()
+        .to_string();
+    let mut length = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse().map_err(io::Error::other)?;
+            }
+        }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    if status != "200" {
+        return Err(io::Error::other(format!("HTTP status {}", status)));
+    }
+    Ok(body)
+}
@@ -0,0 +1,379 @@
+//! 压测：为每种传输启动一个 server 进程，用多个并发客户端发送混合请求，
+//! 统计吞吐量和延迟分位数，输出 JSON 报告。
+//!
+//! Run with `cargo run --release -p json-rpc --bin bench -- --clients 16`.
+
+mod connection;
+mod report;
+
+use clap::{Parser, ValueEnum};
+use connection::Connection;
+use json_rpc::{Framing, RpcError};
+use report::{Config, Report, Samples, TransportReport};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::{self, Child, Command, Stdio};
+use std::sync::Barrier;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long a freshly spawned server gets to start listening.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Transport {
+    Tcp,
+    Unix,
+    Http,
+    Ws,
+    Stdio,
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.to_possible_value().expect("no variant is skipped");
+        f.write_str(value.get_name())
+    }
+}
+
+#[derive(Parser)]
+#[command(about = "Load-tests the JSON-RPC server and prints a JSON report")]
+struct Cli {
+    /// Transports to benchmark, in order (default: all)
+    #[arg(long, value_name = "NAME", value_delimiter = ',')]
+    transport: Vec<Transport>,
+
+    /// Number of concurrent clients
+    #[arg(long, value_name = "N", default_value_t = 8)]
+    clients: usize,
+
+    /// Measured requests per client
+    #[arg(long, value_name = "N", default_value_t = 500)]
+    requests: usize,
+
+    /// Unmeasured requests per client before measuring starts
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    warmup: usize,
+
+    /// Message framing for tcp, unix and stdio: `newline` or `content-length`
+    #[arg(long, default_value_t = Framing::Newline)]
+    framing: Framing,
+
+    /// Passed to the server as `--workers`
+    #[arg(long, value_name = "N")]
+    workers: Option<usize>,
+
+    /// Server program to spawn; defaults to the `server` binary next to this one
+    #[arg(long, value_name = "PROGRAM")]
+    server: Option<PathBuf>,
+
+    /// Write the JSON report to this file instead of stdout
+    #[arg(long, short, value_name = "PATH")]
+    output: Option<PathBuf>,
+}
+
+/// One kind of call in the mix; `weight` is its share of the requests.
+struct Op {
+    name: &'static str,
+    method: &'static str,
+    weight: usize,
+    params: fn(usize) -> Value,
+}
+
+const OPS: [Op; 4] = [
+    Op {
+        name: "echo",
+        method: "echo",
+        weight: 4,
+        params: |_| json!({"text": "hello"}),
+    },
+    Op {
+        name: "echo-4k",
+        method: "echo",
+        weight: 1,
+        params: |_| json!({"data": "x".repeat(4096)}),
+    },
+    Op {
+        name: "add",
+        method: "add",
+        weight: 4,
+        params: |i| json!({"a": i, "b": 1}),
+    },
+    Op {
+        name: "countdown",
+        method: "countdown",
+        weight: 1,
+        params: |_| json!({"from": 1, "interval_ms": 0}),
+    },
+];
+
+/// Where the clients of one run connect to.
+enum Endpoint {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+    Http(SocketAddr),
+    Ws(SocketAddr),
+    /// Every client spawns a server of its own.
+    Stdio,
+}
+
+impl Endpoint {
+    fn connect(&self, cli: &Cli) -> io::Result<Connection> {
+        match self {
+            Endpoint::Tcp(addr) => Connection::tcp(*addr, cli.framing),
+            Endpoint::Unix(path) => Connection::unix(path, cli.framing),
+            Endpoint::Http(addr) => Connection::http(*addr),
+            Endpoint::Ws(addr) => Connection::websocket(*addr),
+            Endpoint::Stdio => Connection::stdio(&mut server_command(cli), cli.framing),
+        }
+    }
+
+    fn is_listening(&self) -> bool {
+        match self {
+            Endpoint::Tcp(addr) | Endpoint::Http(addr) | Endpoint::Ws(addr) => {
+                TcpStream::connect(addr).is_ok()
+            }
+            #[cfg(unix)]
+            Endpoint::Unix(path) => std::os::unix::net::UnixStream::connect(path).is_ok(),
+            #[cfg(not(unix))]
+            Endpoint::Unix(_) => false,
+            Endpoint::Stdio => true,
+        }
+    }
+}
+
+/// A server process that listens on an endpoint; killed when dropped.
+struct Listener {
+    child: Child,
+    endpoint: Endpoint,
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        if let Endpoint::Unix(path) = &self.endpoint {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn server_command(cli: &Cli) -> Command {
+    let program = match &cli.server {
+        Some(program) => program.clone(),
+        None => {
+            let exe = env::current_exe().unwrap_or_default();
+            exe.with_file_name(format!("server{}", env::consts::EXE_SUFFIX))
+        }
+    };
+    let mut command = Command::new(program);
+    // 日志会拖慢被测的 server，除非显式要求
+    if env::var_os("RPC_LOG").is_none() {
+        command.env("RPC_LOG", "error");
+    }
+    command.args(["--framing", &cli.framing.to_string()]);
+    if let Some(workers) = cli.workers {
+        command.args(["--workers", &workers.to_string()]);
+    }
+    command
+}
+
+fn free_addr() -> io::Result<SocketAddr> {
+    // 先绑定端口 0 拿到一个空闲端口，再交给 server 使用
+    TcpListener::bind("127.0.0.1:0")?.local_addr()
+}
+
+/// Starts a server listening on `transport`, or returns `None` for stdio.
+fn launch(cli: &Cli, transport: Transport) -> io::Result<Option<Listener>> {
+    let (flag, endpoint) = match transport {
+        Transport::Tcp => ("--tcp", Endpoint::Tcp(free_addr()?)),
+        Transport::Http => ("--http", Endpoint::Http(free_addr()?)),
+        Transport::Ws => ("--ws", Endpoint::Ws(free_addr()?)),
+        Transport::Unix => {
+            let path = env::temp_dir().join(format!("json-rpc-bench-{}.sock", process::id()));
+            ("--unix", Endpoint::Unix(path))
+        }
+        Transport::Stdio => return Ok(None),
+    };
+    let target = match &endpoint {
+        Endpoint::Unix(path) => path.display().to_string(),
+        Endpoint::Tcp(addr) | Endpoint::Http(addr) | Endpoint::Ws(addr) => addr.to_string(),
+        Endpoint::Stdio => unreachable!(),
+    };
+    let child = server_command(cli)
+        .args([flag, &target])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .spawn()?;
+    let mut listener = Listener { child, endpoint };
+
+    let started = Instant::now();
+    while !listener.endpoint.is_listening() {
+        if let Some(status) = listener.child.try_wait()? {
+            return Err(io::Error::other(format!("server exited with {}", status)));
+        }
+        if started.elapsed() > STARTUP_TIMEOUT {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("server is not listening on {}", target),
+            ));
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    Ok(Some(listener))
+}
+
+/// Runs one client: warm up, wait for the others, then issue the measured calls.
+fn client(
+    cli: &Cli,
+    endpoint: &Endpoint,
+    index: usize,
+    start: &Barrier,
+) -> io::Result<BTreeMap<&'static str, Samples>> {
+    let schedule: Vec<&Op> = OPS
+        .iter()
+        .flat_map(|op| std::iter::repeat_n(op, op.weight))
+        .collect();
+    let mut samples = BTreeMap::new();
+    let connection = endpoint.connect(cli).and_then(|mut connection| {
+        for i in 0..cli.warmup {
+            let op = schedule[(index + i) % schedule.len()];
+            call(&mut connection, op, i)?;
+        }
+        Ok(connection)
+    });
+    // 即使连接失败也要到达屏障，否则其他客户端会一直等下去
+    start.wait();
+    let mut connection = connection?;
+
+    for i in 0..cli.requests {
+        let op = schedule[(index + i) % schedule.len()];
+        let started = Instant::now();
+        let ok = call(&mut connection, op, i)?;
+        samples
+            .entry(op.name)
+            .or_insert_with(Samples::default)
+            .record(started.elapsed(), ok);
+    }
+    connection.close()?;
+    Ok(samples)
+}
+
+/// Returns whether the call succeeded; transport failures end the run.
+fn call(connection: &mut Connection, op: &Op, i: usize) -> io::Result<bool> {
+    match connection.call(op.method, &(op.params)(i)) {
+        Ok(_) => Ok(true),
+        Err(err) if err.code == RpcError::TRANSPORT_ERROR => Err(io::Error::other(err)),
+        Err(_) => Ok(false),
+    }
+}
+
+fn run_transport(cli: &Cli, transport: Transport) -> io::Result<TransportReport> {
+    let listener = launch(cli, transport)?;
+    let endpoint = listener
+        .as_ref()
+        .map_or(&Endpoint::Stdio, |listener| &listener.endpoint);
+    let start = &Barrier::new(cli.clients + 1);
+
+    let (elapsed, results) = thread::scope(|scope| {
+        let clients: Vec<_> = (0..cli.clients)
+            .map(|index| scope.spawn(move || client(cli, endpoint, index, start)))
+            .collect();
+        start.wait();
+        let started = Instant::now();
+        let results: Vec<_> = clients
+            .into_iter()
+            .map(|client| {
+                client
+                    .join()
+                    .unwrap_or_else(|_| Err(io::Error::other("client thread panicked")))
+            })
+            .collect();
+        (started.elapsed(), results)
+    });
+
+    let mut samples: BTreeMap<&str, Samples> = BTreeMap::new();
+    for result in results {
+        for (name, more) in result? {
+            samples.entry(name).or_default().merge(more);
+        }
+    }
+    Ok(TransportReport::new(
+        transport.to_string(),
+        elapsed,
+        samples,
+    ))
+}
+
+fn print_summary(report: &TransportReport) {
+    let latency = &report.total.latency_us;
+    eprintln!(
+        "{:<6} {:>8} {:>7} {:>10.0} {:>9.2} {:>9.2} {:>9.2}",
+        report.transport,
+        report.total.requests,
+        report.total.errors,
+        report.throughput,
+        latency.p50 as f64 / 1000.0,
+        latency.p90 as f64 / 1000.0,
+        latency.p99 as f64 / 1000.0
+    );
+}
+
+fn run(cli: &Cli) -> io::Result<Report> {
+    let transports = if cli.transport.is_empty() {
+        Transport::value_variants().to_vec()
+    } else {
+        cli.transport.clone()
+    };
+    eprintln!(
+        "{:<6} {:>8} {:>7} {:>10} {:>9} {:>9} {:>9}",
+        "", "requests", "errors", "req/s", "p50 ms", "p90 ms", "p99 ms"
+    );
+    let mut reports = Vec::new();
+    for transport in transports {
+        let report = run_transport(cli, transport)
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", transport, err)))?;
+        print_summary(&report);
+        reports.push(report);
+    }
+    Ok(Report {
+        version: env!("CARGO_PKG_VERSION"),
+        config: Config {
+            clients: cli.clients,
+            requests: cli.requests,
+            warmup: cli.warmup,
+            framing: cli.framing.to_string(),
+            workers: cli.workers,
+        },
+        transports: reports,
+    })
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let report = match run(&cli) {
+        Ok(report) => report,
+        Err(err) => {
+            eprintln!("bench: {}", err);
+            process::exit(1);
+        }
+    };
+    let json = serde_json::to_string_pretty(&report).expect("reports always serialize");
+    let written = match &cli.output {
+        Some(path) => fs::write(path, json + "\n"),
+        None => {
+            println!("{}", json);
+            Ok(())
+        }
+    };
+    if let Err(err) = written {
+        eprintln!("bench: {}", err);
+        process::exit(1);
+    }
+}
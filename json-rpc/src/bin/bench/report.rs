@@ -0,0 +1,117 @@
+//! 压测结果：吞吐量和延迟分位数，序列化成 JSON 方便跨版本对比。
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Latencies of one kind of call, in the order they were measured.
+#[derive(Default)]
+pub struct Samples {
+    latencies: Vec<Duration>,
+    errors: u64,
+}
+
+impl Samples {
+    pub fn record(&mut self, latency: Duration, ok: bool) {
+        self.latencies.push(latency);
+        if !ok {
+            self.errors += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: Samples) {
+        self.latencies.extend(other.latencies);
+        self.errors += other.errors;
+    }
+
+    pub fn summary(&self) -> Summary {
+        let mut sorted = self.latencies.clone();
+        sorted.sort();
+        let micros = |latency: &Duration| latency.as_micros() as u64;
+        // 最近秩法：第 p 百分位是排序后第 ceil(p * n) 个样本
+        let percentile = |p: f64| {
+            let rank = (p * sorted.len() as f64).ceil() as usize;
+            sorted.get(rank.saturating_sub(1)).map_or(0, micros)
+        };
+        let total: Duration = sorted.iter().sum();
+        Summary {
+            requests: sorted.len() as u64,
+            errors: self.errors,
+            latency_us: Latency {
+                min: sorted.first().map_or(0, micros),
+                mean: if sorted.is_empty() {
+                    0
+                } else {
+                    micros(&total) / sorted.len() as u64
+                },
+                p50: percentile(0.50),
+                p90: percentile(0.90),
+                p99: percentile(0.99),
+                max: sorted.last().map_or(0, micros),
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Latency {
+    pub min: u64,
+    pub mean: u64,
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub max: u64,
+}
+
+#[derive(Serialize)]
+pub struct Summary {
+    pub requests: u64,
+    pub errors: u64,
+    pub latency_us: Latency,
+}
+
+#[derive(Serialize)]
+pub struct TransportReport {
+    pub transport: String,
+    pub elapsed_ms: u64,
+    /// Completed calls per second, errors included.
+    pub throughput: f64,
+    #[serde(flatten)]
+    pub total: Summary,
+    pub methods: BTreeMap<String, Summary>,
+}
+
+impl TransportReport {
+    pub fn new(transport: String, elapsed: Duration, samples: BTreeMap<&str, Samples>) -> Self {
+        let mut all = Samples::default();
+        let mut methods = BTreeMap::new();
+        for (name, samples) in samples {
+            methods.insert(name.to_string(), samples.summary());
+            all.merge(samples);
+        }
+        let total = all.summary();
+        TransportReport {
+            transport,
+            elapsed_ms: elapsed.as_millis() as u64,
+            throughput: total.requests as f64 / elapsed.as_secs_f64(),
+            total,
+            methods,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Config {
+    pub clients: usize,
+    pub requests: usize,
+    pub warmup: usize,
+    pub framing: String,
+    pub workers: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct Report {
+    pub version: &'static str,
+    pub config: Config,
+    pub transports: Vec<TransportReport>,
+}
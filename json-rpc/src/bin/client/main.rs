@@ -0,0 +1,307 @@
+mod repl;
+
+use clap::Parser;
+use json_rpc::client::Update;
+use json_rpc::schemars::JsonSchema;
+use json_rpc::{tls, Client, Context, Encoding, Framing, RpcError};
+use serde::Deserialize;
+use serde_json::Value;
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::process::{self, Command};
+use std::time::Duration;
+
+/// Exit code when the server answered with an error.
+const EXIT_RPC_ERROR: i32 = 1;
+/// Exit code for bad arguments or unreadable params (same as clap).
+const EXIT_USAGE: i32 = 2;
+/// Exit code when the server could not be reached or did not exit cleanly.
+const EXIT_TRANSPORT: i32 = 3;
+
+#[derive(Parser)]
+#[command(
+    about = "Calls a method on a JSON-RPC server and prints the result",
+    after_help = "Exit status: 0 on success, 1 if the server returned an error, \
+                  2 for usage errors, 3 if the server could not be reached or did not \
+                  shut down cleanly."
+)]
+struct Cli {
+    /// Method to call, e.g. `add`
+    #[arg(required_unless_present = "repl")]
+    method: Option<String>,
+
+    /// JSON params, e.g. '{"a": 1, "b": 2}' or '[1, 2]'
+    params: Option<String>,
+
+    /// Read the JSON params from a file (`-` for stdin)
+    #[arg(long, value_name = "FILE", conflicts_with = "params")]
+    params_file: Option<PathBuf>,
+
+    /// Server program to spawn; defaults to the `server` binary next to this one
+    #[arg(long, value_name = "PROGRAM", conflicts_with_all = ["tcp", "unix"])]
+    command: Option<PathBuf>,
+
+    /// Argument passed to the spawned server (repeatable)
+    #[arg(long = "arg", value_name = "ARG", allow_hyphen_values = true)]
+    args: Vec<String>,
+
+    /// Connect to a server listening on this TCP address
+    #[arg(long, value_name = "ADDR", conflicts_with = "unix")]
+    tcp: Option<String>,
+
+    /// Connect over TLS, trusting the server certificates signed by a CA in
+    /// this PEM file (needs `--tcp`)
+    #[arg(long, value_name = "PATH", requires = "tcp")]
+    tls_ca: Option<PathBuf>,
+
+    /// Present this PEM client certificate (mutual TLS)
+    #[arg(long, value_name = "PATH", requires_all = ["tls_ca", "tls_key"])]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM private key for `--tls-cert`
+    #[arg(long, value_name = "PATH", requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+
+    /// Name the server certificate must be valid for; defaults to the host
+    /// in `--tcp`
+    #[arg(long, value_name = "NAME", requires = "tls_ca")]
+    tls_server_name: Option<String>,
+
+    /// Connect to a server listening on this Unix domain socket
+    #[arg(long, value_name = "PATH")]
+    unix: Option<PathBuf>,
+
+    /// Message framing: `newline` or `content-length`
+    #[arg(long, default_value_t = Framing::Newline)]
+    framing: Framing,
+
+    /// Message encoding: `json`, `msgpack` or `cbor` (binary encodings need
+    /// `--framing content-length`)
+    #[arg(long, default_value_t = Encoding::Json)]
+    encoding: Encoding,
+
+    /// Send a notification and exit without waiting for a response
+    #[arg(long)]
+    notify: bool,
+
+    /// Print `$/progress` updates to stderr while waiting for the result
+    #[arg(long, conflicts_with_all = ["notify", "timeout"])]
+    progress: bool,
+
+    /// Give up on the call, and on the shutdown handshake after it, after this
+    /// many milliseconds
+    #[arg(long, value_name = "MS")]
+    timeout: Option<u64>,
+
+    /// Authenticate with this token (sent as `meta.token`)
+    #[arg(long, value_name = "TOKEN")]
+    token: Option<String>,
+
+    /// Answer yes when the server asks for confirmation (otherwise ask on
+    /// the terminal, or answer no if there is none)
+    #[arg(long, short = 'y')]
+    yes: bool,
+
+    /// Print the result on one line instead of pretty-printing it
+    #[arg(long)]
+    compact: bool,
+
+    /// Start an interactive console instead of making a single call
+    #[arg(long, conflicts_with_all = ["params", "params_file", "notify"])]
+    repl: bool,
+}
+
+fn fail(code: i32, message: impl std::fmt::Display) -> ! {
+    eprintln!("client: {}", message);
+    process::exit(code);
+}
+
+fn read_params(cli: &Cli) -> io::Result<Option<String>> {
+    match &cli.params_file {
+        Some(path) if path.as_os_str() == "-" => {
+            let mut params = String::new();
+            io::stdin().read_to_string(&mut params)?;
+            Ok(Some(params))
+        }
+        Some(path) => fs::read_to_string(path).map(Some),
+        None => Ok(cli.params.clone()),
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct ConfirmParams {
+    message: String,
+}
+
+fn confirm(message: &str, yes: bool) -> bool {
+    if yes {
+        return true;
+    }
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        return false;
+    }
+    eprint!("{} [y/N] ", message);
+    let _ = io::stderr().flush();
+    let mut answer = String::new();
+    stdin.read_line(&mut answer).is_ok() && answer.trim().eq_ignore_ascii_case("y")
+}
+
+fn call_with_progress(client: &Client, method: &str, params: Value) -> Result<Value, RpcError> {
+    let mut result = Err(RpcError::transport("connection closed"));
+    for update in client.call_streaming(method, params)? {
+        match update {
+            Update::Progress(value) => eprintln!("progress: {}", format(&value, true)),
+            Update::Done(done) => result = done,
+        }
+    }
+    result
+}
+
+fn connect(cli: &Cli) -> io::Result<Client> {
+    let client = open(cli)?.with_encoding(cli.encoding);
+    if let Some(token) = &cli.token {
+        client.set_token(token);
+    }
+    // 服务端可以反过来请求确认
+    let yes = cli.yes;
+    client.on_request("confirm", move |_: &Context, p: ConfirmParams| {
+        Ok::<_, RpcError>(confirm(&p.message, yes))
+    });
+    Ok(client)
+}
+
+/// The host part of `host:port`, without the brackets around IPv6 addresses.
+fn host(addr: &str) -> &str {
+    let host = addr.rsplit_once(':').map_or(addr, |(host, _)| host);
+    host.trim_start_matches('[').trim_end_matches(']')
+}
+
+fn open(cli: &Cli) -> io::Result<Client> {
+    if let Some(addr) = &cli.tcp {
+        let Some(ca) = &cli.tls_ca else {
+            return Client::connect_tcp(addr.as_str(), cli.framing);
+        };
+        let identity = cli.tls_cert.as_deref().zip(cli.tls_key.as_deref());
+        let config = tls::client_config(ca, identity)?;
+        let server_name = match &cli.tls_server_name {
+            Some(name) => name.as_str(),
+            None => host(addr),
+        };
+        return Client::connect_tls(addr.as_str(), server_name, config, cli.framing);
+    }
+    #[cfg(unix)]
+    if let Some(path) = &cli.unix {
+        return Client::connect_unix(path, cli.framing);
+    }
+    #[cfg(not(unix))]
+    if cli.unix.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Unix sockets are not supported",
+        ));
+    }
+
+    let mut command = match &cli.command {
+        Some(program) => Command::new(program),
+        None => {
+            // 默认启动与本程序放在同一目录下的 server
+            let exe = env::current_exe()?;
+            let mut command =
+                Command::new(exe.with_file_name(format!("server{}", env::consts::EXE_SUFFIX)));
+            command.args(["--framing", &cli.framing.to_string()]);
+            command
+        }
+    };
+    command.args(&cli.args);
+    Client::spawn(&mut command, cli.framing)
+}
+
+fn format(value: &Value, compact: bool) -> String {
+    let text = if compact {
+        serde_json::to_string(value)
+    } else {
+        serde_json::to_string_pretty(value)
+    };
+    text.expect("JSON values always serialize")
+}
+
+/// Runs the `shutdown`/`exit` handshake, giving up after `timeout` if one
+/// is given. Returns `false`, after reporting why on stderr, if it failed or
+/// a spawned server exited with an error.
+fn finish(client: Client, timeout: Option<Duration>) -> bool {
+    let finished = match timeout {
+        // 超时的请求可能还在服务端运行，shutdown 要等它结束才回复
+        Some(timeout) => client.shutdown_with_timeout(timeout),
+        None => client.shutdown(),
+    };
+    match finished {
+        Ok(Some(status)) if !status.success() => {
+            eprintln!("client: server exited with {}", status);
+            false
+        }
+        Ok(_) => true,
+        Err(err) => {
+            eprintln!("client: shutdown failed: {}", err);
+            false
+        }
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if cli.repl {
+        let client = connect(&cli)
+            .unwrap_or_else(|err| fail(EXIT_TRANSPORT, format!("cannot connect: {}", err)));
+        if let Err(err) = repl::run(&client) {
+            fail(EXIT_TRANSPORT, err);
+        }
+        if !finish(client, None) {
+            process::exit(EXIT_TRANSPORT);
+        }
+        return;
+    }
+    let method = cli.method.as_deref().expect("clap requires a method");
+    let params = match read_params(&cli) {
+        Ok(Some(params)) => match serde_json::from_str::<Value>(&params) {
+            Ok(params) => params,
+            Err(err) => fail(EXIT_USAGE, format!("params are not valid JSON: {}", err)),
+        },
+        Ok(None) => Value::Null,
+        Err(err) => fail(EXIT_USAGE, format!("cannot read params: {}", err)),
+    };
+    let client = connect(&cli)
+        .unwrap_or_else(|err| fail(EXIT_TRANSPORT, format!("cannot connect: {}", err)));
+
+    let result = if cli.notify {
+        client.notify(method, params).map(|()| None)
+    } else if cli.progress {
+        call_with_progress(&client, method, params).map(Some)
+    } else {
+        match cli.timeout {
+            Some(timeout) => {
+                client.call_with_timeout(method, params, Duration::from_millis(timeout))
+            }
+            None => client.call(method, params),
+        }
+        .map(Some)
+    };
+    let finished = finish(client, cli.timeout.map(Duration::from_millis));
+
+    match result {
+        Ok(Some(value)) => println!("{}", format(&value, cli.compact)),
+        Ok(None) => {}
+        Err(err) if err.code == RpcError::TRANSPORT_ERROR => fail(EXIT_TRANSPORT, err),
+        Err(err) => {
+            // 错误对象写到 stderr，stdout 只放成功的结果
+            let error = serde_json::to_value(&err).unwrap_or_default();
+            eprintln!("{}", format(&error, cli.compact));
+            process::exit(EXIT_RPC_ERROR);
+        }
+    }
+    if !finished {
+        process::exit(EXIT_TRANSPORT);
+    }
+}
@@ -0,0 +1,145 @@
+//! 交互式控制台：`add {"a":1,"b":2}`，支持历史记录和方法名补全。
+
+use json_rpc::{Client, RpcError};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+use rustyline::{Editor, Helper, Highlighter, Hinter, Validator};
+use serde_json::Value;
+use std::env;
+use std::path::PathBuf;
+
+const HELP: &str = "\
+Type `<method> [json params]` to call a method, e.g. `add {\"a\": 1, \"b\": 2}`.
+Commands:
+  .methods   list the server's methods
+  .help      show this help
+  .quit      leave the console (or press Ctrl-D)";
+
+#[derive(Helper, Hinter, Highlighter, Validator)]
+struct MethodCompleter {
+    methods: Vec<String>,
+}
+
+impl Completer for MethodCompleter {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let prefix = &line[..pos];
+        // 只补全第一个词，也就是方法名
+        if prefix.contains(char::is_whitespace) {
+            return Ok((pos, Vec::new()));
+        }
+        let candidates = self
+            .methods
+            .iter()
+            .filter(|method| method.starts_with(prefix))
+            .map(|method| Pair {
+                display: method.clone(),
+                replacement: format!("{} ", method),
+            })
+            .collect();
+        Ok((0, candidates))
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".json_rpc_history"))
+}
+
+fn discover_methods(client: &Client) -> Vec<String> {
+    match client.call::<_, Vec<String>>("rpc.methods", ()) {
+        Ok(methods) => methods,
+        Err(err) => {
+            eprintln!("could not list methods: {}", err);
+            Vec::new()
+        }
+    }
+}
+
+fn parse_line(line: &str) -> Result<(&str, Value), String> {
+    let (method, params) = match line.split_once(char::is_whitespace) {
+        Some((method, params)) => (method, params.trim()),
+        None => (line, ""),
+    };
+    if params.is_empty() {
+        return Ok((method, Value::Null));
+    }
+    serde_json::from_str(params)
+        .map(|params| (method, params))
+        .map_err(|err| format!("params are not valid JSON: {}", err))
+}
+
+fn print_result(result: Result<Value, RpcError>) {
+    match result {
+        Ok(value) => println!(
+            "{}",
+            serde_json::to_string_pretty(&value).unwrap_or_default()
+        ),
+        Err(err) => {
+            let error = serde_json::to_value(&err).unwrap_or_default();
+            println!(
+                "error: {}",
+                serde_json::to_string_pretty(&error).unwrap_or_default()
+            );
+        }
+    }
+}
+
+pub fn run(client: &Client) -> rustyline::Result<()> {
+    let methods = discover_methods(client);
+    let mut editor: Editor<MethodCompleter, DefaultHistory> = Editor::new()?;
+    editor.set_helper(Some(MethodCompleter {
+        methods: methods.clone(),
+    }));
+    let history = history_path();
+    if let Some(path) = &history {
+        // 第一次运行时历史文件还不存在
+        let _ = editor.load_history(path);
+    }
+    println!(
+        "Connected, {} methods available. Type .help for help.",
+        methods.len()
+    );
+
+    loop {
+        let line = match editor.readline("rpc> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(err),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line)?;
+        match line {
+            ".quit" | ".exit" => break,
+            ".help" => println!("{}", HELP),
+            ".methods" => {
+                let methods = discover_methods(client);
+                for method in &methods {
+                    println!("{}", method);
+                }
+                if let Some(helper) = editor.helper_mut() {
+                    helper.methods = methods;
+                }
+            }
+            line => match parse_line(line) {
+                Ok((method, params)) => print_result(client.call(method, params)),
+                Err(err) => println!("{}", err),
+            },
+        }
+    }
+
+    if let Some(path) = &history {
+        editor.save_history(path)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,146 @@
+//! 发出请求的一方：分配递增 id，把（可能乱序到达的）响应交给等待它的调用。
+//! 客户端和服务端的会话共用这一套逻辑。
+
+use crate::error::RpcError;
+use crate::message::{Request, Response};
+use crate::router::CANCEL_METHOD;
+use log::warn;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// `()` and `None` serialize to `null`, which means "no params".
+pub(crate) fn structured(params: Value) -> Option<Value> {
+    (!params.is_null()).then_some(params)
+}
+
+/// What the peer sends back for one call: any number of progress values,
+/// then the response.
+pub(crate) enum Delivery {
+    Progress(Value),
+    Response(Response),
+}
+
+/// Requests sent to the peer that are still waiting for a response.
+pub(crate) struct Calls {
+    next_id: AtomicU64,
+    closed: AtomicBool,
+    pending: Mutex<HashMap<u64, Sender<Delivery>>>,
+}
+
+impl Default for Calls {
+    fn default() -> Self {
+        Calls {
+            next_id: AtomicU64::new(1),
+            closed: AtomicBool::new(false),
+            pending: Mutex::default(),
+        }
+    }
+}
+
+impl Calls {
+    /// Sends a request through `send`; its progress and response arrive on
+    /// the returned receiver.
+    pub(crate) fn start(
+        &self,
+        method: &str,
+        params: Option<Value>,
+        send: impl Fn(&Request) -> Result<(), RpcError>,
+    ) -> Result<(u64, Receiver<Delivery>), RpcError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = mpsc::channel();
+        self.pending.lock().unwrap().insert(id, sender);
+        // 先登记再检查，避免与 close() 竞争时永远等不到响应
+        if self.closed.load(Ordering::SeqCst) {
+            self.forget(id);
+            return Err(RpcError::transport("connection closed"));
+        }
+
+        if let Err(err) = send(&Request::new(method, params, Some(Value::from(id)))) {
+            self.forget(id);
+            return Err(err);
+        }
+        Ok((id, receiver))
+    }
+
+    /// Sends a request through `send` and waits for its response, ignoring
+    /// progress. After `timeout` the call is forgotten and the peer is told
+    /// to cancel it.
+    pub(crate) fn call(
+        &self,
+        method: &str,
+        params: Option<Value>,
+        timeout: Option<Duration>,
+        send: impl Fn(&Request) -> Result<(), RpcError>,
+    ) -> Result<Value, RpcError> {
+        let (id, receiver) = self.start(method, params, &send)?;
+        let deadline = timeout.map(|timeout| (timeout, Instant::now() + timeout));
+        loop {
+            let delivery = match deadline {
+                Some((timeout, deadline)) => {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    match receiver.recv_timeout(left) {
+                        Ok(delivery) => delivery,
+                        Err(RecvTimeoutError::Timeout) => {
+                            self.forget(id);
+                            let cancel = Request::new(CANCEL_METHOD, Some(json!({"id": id})), None);
+                            let _ = send(&cancel);
+                            return Err(RpcError::timeout(timeout));
+                        }
+                        Err(RecvTimeoutError::Disconnected) => {
+                            return Err(RpcError::transport("connection closed"))
+                        }
+                    }
+                }
+                None => receiver
+                    .recv()
+                    .map_err(|_| RpcError::transport("connection closed"))?,
+            };
+            if let Delivery::Response(response) = delivery {
+                return response.into_result();
+            }
+        }
+    }
+
+    fn forget(&self, id: u64) {
+        self.pending.lock().unwrap().remove(&id);
+    }
+
+    /// Hands `response` to the call waiting for its id.
+    pub(crate) fn resolve(&self, response: Response) {
+        let sender = response
+            .id
+            .as_u64()
+            .and_then(|id| self.pending.lock().unwrap().remove(&id));
+        match sender {
+            Some(sender) => {
+                let _ = sender.send(Delivery::Response(response));
+            }
+            None => warn!("response for unknown request id {}", response.id),
+        }
+    }
+
+    /// Hands a `$/progress` value to the call whose id is `token`. Returns
+    /// `false` if no call is waiting for it.
+    pub(crate) fn progress(&self, token: &Value, value: Value) -> bool {
+        let pending = self.pending.lock().unwrap();
+        match token.as_u64().and_then(|id| pending.get(&id)) {
+            Some(sender) => sender.send(Delivery::Progress(value)).is_ok(),
+            None => false,
+        }
+    }
+
+    /// The connection is gone: every waiting call fails with a transport
+    /// error, and so does every later one.
+    pub(crate) fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.pending.lock().unwrap().clear();
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+}
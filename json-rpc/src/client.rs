@@ -1,37 +1,507 @@
-use serde_json::json;
-use std::io::{BufRead, BufReader, Write};
-use std::process::{Command, Stdio};
-
-fn main() {
-    let mut child = Command::new("cargo")
-        .args(&["run", "--bin", "server"])
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .spawn()
-        .expect("Failed to start server process");
+//! 可复用的客户端：分配递增 id，按 id 匹配（可能乱序到达的）响应。
+//! 服务端也可以反过来调用客户端注册的方法。
 
-    {
-        let stdin = child.stdin.as_mut().expect("Failed to open stdin");
+use crate::calls::{structured, Delivery};
+use crate::encoding::Encoding;
+use crate::error::RpcError;
+use crate::framing::Framing;
+use crate::message::{Request, Response};
+use crate::router::{
+    Router, CANCEL_METHOD, EXIT_METHOD, INITIALIZE_METHOD, PROGRESS_METHOD, SHUTDOWN_METHOD,
+};
+use crate::session::{Context, Session};
+use crate::tls;
+use log::{debug, warn};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufReader, Read, Write};
+use std::net::Shutdown;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+type NotificationHandler = Box<dyn Fn(Value) + Send>;
+
+/// The write half, shared by calls and by replies to the server's requests.
+struct Outbound {
+    writer: Option<Box<dyn Write + Send>>,
+    framing: Framing,
+    encoding: Encoding,
+    meta: Option<Value>,
+}
+
+impl Outbound {
+    fn write(&mut self, mut message: Value) -> Result<(), RpcError> {
+        if let (Some(meta), Value::Object(object)) = (&self.meta, &mut message) {
+            if object.contains_key("method") {
+                object.insert("meta".to_string(), meta.clone());
+            }
+        }
+        let payload = self
+            .encoding
+            .encode(&message)
+            .map_err(RpcError::internal_error)?;
+        let writer = self
+            .writer
+            .as_mut()
+            .ok_or_else(|| RpcError::transport("client is closed"))?;
+        let framer = self.framing.framer();
+        let written = if self.encoding.is_binary() {
+            framer.write_typed_frame(writer, &payload, self.encoding.content_type())
+        } else {
+            framer.write_frame(writer, &payload)
+        };
+        written.map_err(RpcError::transport)
+    }
+}
+
+struct Shared {
+    session: Arc<Session>,
+    router: RwLock<Router>,
+    handlers: Mutex<HashMap<String, NotificationHandler>>,
+}
+
+impl Shared {
+    fn receive(self: &Arc<Self>, message: Value) {
+        match message {
+            Value::Array(batch) => batch.into_iter().for_each(|message| self.receive(message)),
+            message if message.get("method").is_none() => {
+                match serde_json::from_value::<Response>(message) {
+                    Ok(response) => self.session.calls().resolve(response),
+                    Err(err) => warn!("ignoring malformed response: {}", err),
+                }
+            }
+            message if message.get("id").is_some() => self.dispatch(message),
+            message => self.notification(message),
+        }
+    }
 
-        let request = json!({
-            "method": "echo",
-            "params": {"text": "Hello, JSON-RPC!"}
+    /// Runs a request or notification from the server on its own thread, so
+    /// the handler can call back into the server.
+    fn dispatch(self: &Arc<Self>, message: Value) {
+        let shared = Arc::clone(self);
+        thread::spawn(move || {
+            let response = shared
+                .router
+                .read()
+                .unwrap()
+                .handle_value(&shared.session, message);
+            if let Some(response) = response.and_then(|r| serde_json::to_value(r).ok()) {
+                shared.session.send(response);
+            }
         });
-        writeln!(stdin, "{}", request.to_string()).expect("Failed to write to stdin");
     }
 
-    if let Some(stdout) = child.stdout.as_mut() {
-        let stdout_reader = BufReader::new(stdout);
-        for line in stdout_reader.lines() {
-            if let Ok(line) = line {
-                // 打印服务端返回的响应
-                println!("Server response: {}", line);
-                if let Ok(response) = serde_json::from_str::<serde_json::Value>(&line) {
-                    println!("Response: {}", response);
+    fn notification(self: &Arc<Self>, message: Value) {
+        let method = message["method"].as_str().unwrap_or_default();
+        if method == PROGRESS_METHOD {
+            let params = message.get("params").unwrap_or(&Value::Null);
+            let (token, value) = (&params["token"], params["value"].clone());
+            if self.session.calls().progress(token, value) {
+                return;
+            }
+        }
+        if let Some(handler) = self.handlers.lock().unwrap().get(method) {
+            handler(message.get("params").cloned().unwrap_or(Value::Null));
+            return;
+        }
+        if method == CANCEL_METHOD {
+            // 不经过路由表的锁：被取消的处理函数可能正持有它
+            if let Some(id) = message.get("params").and_then(|params| params.get("id")) {
+                self.session.cancel(id);
+            }
+        } else if self.router.read().unwrap().contains(method) {
+            self.dispatch(message);
+        } else {
+            debug!("unhandled notification {}", method);
+        }
+    }
+}
+
+/// One item of a [`Streaming`] call.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Update {
+    /// A value reported through `$/progress`.
+    Progress(Value),
+    /// The response; always the last item.
+    Done(Result<Value, RpcError>),
+}
+
+/// A call in flight, see [`Client::call_streaming`]. Iterating blocks until
+/// the next update arrives.
+pub struct Streaming {
+    receiver: Option<Receiver<Delivery>>,
+}
+
+impl Streaming {
+    /// Skips the remaining progress and waits for the result.
+    pub fn finish<R: DeserializeOwned>(self) -> Result<R, RpcError> {
+        for update in self {
+            if let Update::Done(result) = update {
+                return serde_json::from_value(result?).map_err(RpcError::internal_error);
+            }
+        }
+        Err(RpcError::transport("connection closed"))
+    }
+}
+
+impl Iterator for Streaming {
+    type Item = Update;
+
+    fn next(&mut self) -> Option<Update> {
+        let update = match self.receiver.as_ref()?.recv() {
+            Ok(Delivery::Progress(value)) => return Some(Update::Progress(value)),
+            Ok(Delivery::Response(response)) => Update::Done(response.into_result()),
+            Err(_) => Update::Done(Err(RpcError::transport("connection closed"))),
+        };
+        self.receiver = None;
+        Some(update)
+    }
+}
+
+/// A JSON-RPC client over any byte stream.
+///
+/// Calls may be issued from several threads at once; a background reader
+/// thread routes each response to the call waiting for its id.
+///
+/// The server may call the client too: requests it sends are served by the
+/// methods registered with [`Client::on_request`] or [`Client::with_router`].
+/// Both directions share one id space, so a handler can itself call the
+/// server through `ctx.session()`.
+pub struct Client {
+    outbound: Arc<Mutex<Outbound>>,
+    shared: Arc<Shared>,
+    reader: Option<JoinHandle<()>>,
+    child: Option<Child>,
+    disconnect: Option<Box<dyn FnOnce() + Send + Sync>>,
+}
+
+impl Client {
+    pub fn new<R, W>(reader: R, writer: W, framing: Framing) -> Self
+    where
+        R: Read + Send + 'static,
+        W: Write + Send + 'static,
+    {
+        let outbound = Arc::new(Mutex::new(Outbound {
+            writer: Some(Box::new(writer)),
+            framing,
+            encoding: Encoding::Json,
+            meta: None,
+        }));
+        let (sender, messages) = mpsc::channel::<Value>();
+        // 回复服务端请求的消息由这个线程写出；会话释放后退出
+        {
+            let outbound = Arc::clone(&outbound);
+            thread::spawn(move || {
+                for message in messages {
+                    debug!("client send: {}", message);
+                    if let Err(err) = outbound.lock().unwrap().write(message) {
+                        warn!("client write failed: {}", err);
+                    }
+                }
+            });
+        }
+        let shared = Arc::new(Shared {
+            session: Session::new(sender),
+            router: RwLock::default(),
+            handlers: Mutex::default(),
+        });
+        let reader = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || {
+                let framer = framing.framer();
+                let mut reader = BufReader::new(reader);
+                loop {
+                    match framer.read_typed_frame(&mut reader) {
+                        Ok(Some((frame, content_type))) => {
+                            let encoding = content_type
+                                .as_deref()
+                                .and_then(Encoding::from_content_type)
+                                .unwrap_or_default();
+                            match encoding.decode(&frame) {
+                                Ok(message) => shared.receive(message),
+                                Err(err) => warn!("ignoring unparsable message: {}", err),
+                            }
+                        }
+                        Ok(None) => break,
+                        Err(err) => {
+                            warn!("client read failed: {}", err);
+                            break;
+                        }
+                    }
                 }
+                // 连接断开：丢弃所有等待中的请求，让调用方拿到传输错误
+                shared.session.calls().close();
+            })
+        };
+        Client {
+            outbound,
+            shared,
+            reader: Some(reader),
+            child: None,
+            disconnect: None,
+        }
+    }
+
+    /// Spawns `command` and talks to it over its stdin/stdout.
+    pub fn spawn(command: &mut Command, framing: Framing) -> io::Result<Self> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let mut client = Client::new(stdout, stdin, framing);
+        client.child = Some(child);
+        Ok(client)
+    }
+
+    pub fn connect_tcp<A: ToSocketAddrs>(addr: A, framing: Framing) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        let writer = stream.try_clone()?;
+        let socket = stream.try_clone()?;
+        let mut client = Client::new(stream, writer, framing);
+        client.disconnect = Some(Box::new(move || {
+            let _ = socket.shutdown(Shutdown::Both);
+        }));
+        Ok(client)
+    }
+
+    /// Connects over TLS, checking that the server's certificate is valid
+    /// for `server_name` (a DNS name or IP address).
+    pub fn connect_tls<A: ToSocketAddrs>(
+        addr: A,
+        server_name: &str,
+        config: Arc<rustls::ClientConfig>,
+        framing: Framing,
+    ) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        let (reader, writer) = tls::connect(stream, server_name, config)?;
+        let socket = reader.socket()?;
+        let mut client = Client::new(reader, writer, framing);
+        client.disconnect = Some(Box::new(move || {
+            let _ = socket.shutdown(Shutdown::Both);
+        }));
+        Ok(client)
+    }
+
+    #[cfg(unix)]
+    pub fn connect_unix<P: AsRef<std::path::Path>>(path: P, framing: Framing) -> io::Result<Self> {
+        let stream = std::os::unix::net::UnixStream::connect(path)?;
+        let writer = stream.try_clone()?;
+        let socket = stream.try_clone()?;
+        let mut client = Client::new(stream, writer, framing);
+        client.disconnect = Some(Box::new(move || {
+            let _ = socket.shutdown(Shutdown::Both);
+        }));
+        Ok(client)
+    }
+
+    /// Sends requests in `encoding`. Binary encodings need
+    /// [`Framing::ContentLength`]; the server switches to the same encoding
+    /// for its replies.
+    pub fn with_encoding(self, encoding: Encoding) -> Self {
+        self.outbound.lock().unwrap().encoding = encoding;
+        self
+    }
+
+    /// Serves the server's requests with `router`, replacing the methods
+    /// registered so far.
+    pub fn with_router(self, router: Router) -> Self {
+        *self.shared.router.write().unwrap() = router;
+        self
+    }
+
+    /// Handles requests (and notifications without an
+    /// [`on_notification`](Client::on_notification) callback) that the
+    /// server sends with `method`. Each runs on its own thread; requests for
+    /// unknown methods are answered with `Method not found`.
+    pub fn on_request<P, R, F>(&self, method: &str, handler: F)
+    where
+        P: DeserializeOwned + JsonSchema,
+        R: Serialize + JsonSchema,
+        F: Fn(&Context, P) -> Result<R, RpcError> + Send + Sync + 'static,
+    {
+        self.shared
+            .router
+            .write()
+            .unwrap()
+            .register_with_context(method, handler);
+    }
+
+    /// Runs `callback` for every notification the server sends with `method`.
+    pub fn on_notification<F>(&self, method: &str, callback: F)
+    where
+        F: Fn(Value) + Send + 'static,
+    {
+        self.shared
+            .handlers
+            .lock()
+            .unwrap()
+            .insert(method.to_string(), Box::new(callback));
+    }
+
+    /// Attaches `meta` to every request and notification sent from now on.
+    pub fn set_meta(&self, meta: Value) {
+        self.outbound.lock().unwrap().meta = structured(meta);
+    }
+
+    /// Sends `token` as `meta.token` for servers that require authentication.
+    pub fn set_token(&self, token: &str) {
+        self.set_meta(json!({"token": token}));
+    }
+
+    fn send(&self, request: &Request) -> Result<(), RpcError> {
+        let message = serde_json::to_value(request).map_err(RpcError::internal_error)?;
+        self.outbound.lock().unwrap().write(message)
+    }
+
+    /// Calls `method` and waits for its response. Server errors are
+    /// returned as-is; connection failures become transport errors.
+    pub fn call<P, R>(&self, method: &str, params: P) -> Result<R, RpcError>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        self.request(method, params, None)
+    }
+
+    /// Like [`Client::call`], but gives up after `timeout` with a
+    /// `Request timed out` error. The server is told to cancel the request.
+    pub fn call_with_timeout<P, R>(
+        &self,
+        method: &str,
+        params: P,
+        timeout: Duration,
+    ) -> Result<R, RpcError>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        self.request(method, params, Some(timeout))
+    }
+
+    /// Number of calls still waiting for a response.
+    pub fn pending(&self) -> usize {
+        self.shared.session.calls().len()
+    }
+
+    fn request<P, R>(
+        &self,
+        method: &str,
+        params: P,
+        timeout: Option<Duration>,
+    ) -> Result<R, RpcError>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let params = serde_json::to_value(params).map_err(RpcError::invalid_params)?;
+        let result =
+            self.shared
+                .session
+                .calls()
+                .call(method, structured(params), timeout, |request| {
+                    self.send(request)
+                })?;
+        serde_json::from_value(result).map_err(RpcError::internal_error)
+    }
+
+    /// Calls `method` without waiting: the returned [`Streaming`] yields the
+    /// values the handler reports with [`Context::progress`], then the result.
+    pub fn call_streaming<P: Serialize>(
+        &self,
+        method: &str,
+        params: P,
+    ) -> Result<Streaming, RpcError> {
+        let params = serde_json::to_value(params).map_err(RpcError::invalid_params)?;
+        let (_, receiver) =
+            self.shared
+                .session
+                .calls()
+                .start(method, structured(params), |request| self.send(request))?;
+        Ok(Streaming {
+            receiver: Some(receiver),
+        })
+    }
+
+    /// Sends a notification; no response is expected.
+    pub fn notify<P: Serialize>(&self, method: &str, params: P) -> Result<(), RpcError> {
+        let params = serde_json::to_value(params).map_err(RpcError::invalid_params)?;
+        self.send(&Request::new(method, structured(params), None))
+    }
+
+    /// Asks the server for its info and capabilities.
+    pub fn initialize(&self) -> Result<Value, RpcError> {
+        self.call(INITIALIZE_METHOD, Value::Null)
+    }
+
+    /// Orderly shutdown: `shutdown` (answered once the server has finished
+    /// this connection's in-flight requests), then `exit`, then closes the
+    /// connection. For a spawned server returns its exit status.
+    pub fn shutdown(self) -> Result<Option<ExitStatus>, RpcError> {
+        self.finish(None)
+    }
+
+    /// Like [`Client::shutdown`], but gives up if `shutdown` is not answered
+    /// within `timeout`, e.g. because a request is still running: the
+    /// connection is closed, a spawned server is killed, and the timeout
+    /// error is returned.
+    pub fn shutdown_with_timeout(self, timeout: Duration) -> Result<Option<ExitStatus>, RpcError> {
+        self.finish(Some(timeout))
+    }
+
+    fn finish(mut self, timeout: Option<Duration>) -> Result<Option<ExitStatus>, RpcError> {
+        match self.request::<_, Value>(SHUTDOWN_METHOD, Value::Null, timeout) {
+            Ok(_) => {}
+            // 不支持生命周期方法的服务端，直接关闭
+            Err(err) if err.code == RpcError::METHOD_NOT_FOUND => {}
+            Err(err) => {
+                // 服务端还在忙：不再等它，免得关闭连接时又等在子进程上
+                if err.code == RpcError::REQUEST_TIMEOUT {
+                    if let Some(child) = self.child.as_mut() {
+                        let _ = child.kill();
+                    }
+                }
+                return Err(err);
             }
         }
+        self.notify(EXIT_METHOD, Value::Null)?;
+        self.teardown().map_err(RpcError::transport)
+    }
+
+    /// Closes the connection and, for a spawned server, waits for it to exit.
+    pub fn close(mut self) -> io::Result<Option<ExitStatus>> {
+        self.teardown()
+    }
+
+    fn teardown(&mut self) -> io::Result<Option<ExitStatus>> {
+        // 关闭写端，服务端读到 EOF 后退出
+        self.outbound.lock().unwrap().writer.take();
+        if let Some(disconnect) = self.disconnect.take() {
+            disconnect();
+        }
+        let status = match self.child.take() {
+            Some(mut child) => Some(child.wait()?),
+            None => None,
+        };
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+        Ok(status)
     }
+}
 
-    let _ = child.wait().expect("Serve wasn't running");
+impl Drop for Client {
+    fn drop(&mut self) {
+        let _ = self.teardown();
+    }
 }
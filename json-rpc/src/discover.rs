@@ -0,0 +1,107 @@
+//! `rpc.discover`：根据注册的处理函数生成 OpenRPC 风格的服务描述。
+//!
+//! See https://spec.open-rpc.org for the document format.
+
+use schemars::gen::SchemaSettings;
+use schemars::JsonSchema;
+use serde_json::{json, Map, Value};
+
+pub const OPENRPC_VERSION: &str = "1.2.6";
+
+/// Params and result schemas captured when a method is registered.
+#[derive(Debug, Clone)]
+pub struct MethodSchema {
+    pub params: Value,
+    pub result: Value,
+    pub description: Option<String>,
+}
+
+impl MethodSchema {
+    pub fn of<P: JsonSchema, R: JsonSchema>() -> Self {
+        MethodSchema {
+            params: schema_for::<P>(),
+            result: schema_for::<R>(),
+            description: None,
+        }
+    }
+
+    /// Renders the OpenRPC method object for `name`.
+    pub fn to_openrpc(&self, name: &str) -> Value {
+        let mut method = Map::new();
+        method.insert("name".to_string(), json!(name));
+        if let Some(description) = &self.description {
+            method.insert("description".to_string(), json!(description));
+        }
+        let (params, structure) = content_descriptors(&self.params);
+        method.insert("params".to_string(), Value::Array(params));
+        method.insert("paramStructure".to_string(), json!(structure));
+        method.insert(
+            "result".to_string(),
+            json!({"name": "result", "schema": self.result}),
+        );
+        Value::Object(method)
+    }
+}
+
+/// 内联所有子 schema，这样每个方法的描述都是自包含的。
+fn schema_for<T: JsonSchema>() -> Value {
+    let settings = SchemaSettings::draft07().with(|settings| {
+        settings.inline_subschemas = true;
+        settings.meta_schema = None;
+    });
+    let schema = settings.into_generator().into_root_schema_for::<T>();
+    serde_json::to_value(schema).unwrap_or(Value::Bool(true))
+}
+
+/// Splits a params schema into OpenRPC content descriptors: struct fields
+/// become named params, tuple items become positional `arg0`, `arg1`, ...
+fn content_descriptors(schema: &Value) -> (Vec<Value>, &'static str) {
+    if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|required| required.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let params = properties
+            .iter()
+            .map(|(name, schema)| {
+                json!({"name": name, "schema": schema, "required": required.contains(&name.as_str())})
+            })
+            .collect();
+        return (params, "either");
+    }
+    if let Some(items) = schema.get("items").and_then(Value::as_array) {
+        let params = items
+            .iter()
+            .enumerate()
+            .map(|(index, schema)| json!({"name": format!("arg{}", index), "schema": schema, "required": true}))
+            .collect();
+        return (params, "by-position");
+    }
+    if schema.get("type") == Some(&json!("null")) {
+        return (Vec::new(), "either");
+    }
+    (
+        vec![json!({"name": "params", "schema": schema, "required": false})],
+        "either",
+    )
+}
+
+/// Builds the whole `rpc.discover` document from `(name, schema)` pairs.
+pub fn document<'a, I>(methods: I) -> Value
+where
+    I: IntoIterator<Item = (&'a str, &'a MethodSchema)>,
+{
+    let methods: Vec<Value> = methods
+        .into_iter()
+        .map(|(name, schema)| schema.to_openrpc(name))
+        .collect();
+    json!({
+        "openrpc": OPENRPC_VERSION,
+        "info": {
+            "title": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "methods": methods,
+    })
+}
@@ -0,0 +1,99 @@
+//! 消息编码：默认 JSON 文本，也可以协商使用 MessagePack 或 CBOR 二进制编码。
+
+use serde_json::Value;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+/// How a message is turned into bytes. Every encoding carries the same
+/// [`Value`], so handlers never see the difference.
+///
+/// Binary encodings need a framing that can carry arbitrary bytes and a
+/// `Content-Type` header, i.e. [`Framing::ContentLength`](crate::Framing)
+/// or HTTP. The server answers in the encoding of the last message it got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Json,
+    MessagePack,
+    Cbor,
+}
+
+fn invalid_data(err: impl fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+impl Encoding {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Encoding::Json => "application/json",
+            Encoding::MessagePack => "application/msgpack",
+            Encoding::Cbor => "application/cbor",
+        }
+    }
+
+    /// Parses a `Content-Type` value; parameters such as `charset` are
+    /// ignored. `application/vscode-jsonrpc` (LSP) is JSON.
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type.split(';').next().unwrap_or("").trim();
+        let mime = mime.to_ascii_lowercase();
+        match mime.as_str() {
+            "application/json" | "application/vscode-jsonrpc" => Some(Encoding::Json),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+                Some(Encoding::MessagePack)
+            }
+            "application/cbor" => Some(Encoding::Cbor),
+            _ => None,
+        }
+    }
+
+    pub fn is_binary(self) -> bool {
+        self != Encoding::Json
+    }
+
+    pub fn encode(self, message: &Value) -> io::Result<Vec<u8>> {
+        match self {
+            Encoding::Json => Ok(serde_json::to_vec(message)?),
+            Encoding::MessagePack => rmp_serde::to_vec_named(message).map_err(invalid_data),
+            Encoding::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(message, &mut bytes).map_err(invalid_data)?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    pub fn decode(self, bytes: &[u8]) -> io::Result<Value> {
+        match self {
+            Encoding::Json => Ok(serde_json::from_slice(bytes)?),
+            Encoding::MessagePack => rmp_serde::from_slice(bytes).map_err(invalid_data),
+            Encoding::Cbor => ciborium::from_reader(bytes).map_err(invalid_data),
+        }
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Encoding::Json),
+            "msgpack" | "messagepack" => Ok(Encoding::MessagePack),
+            "cbor" => Ok(Encoding::Cbor),
+            _ => Err(format!(
+                "unknown encoding `{}`, expected `json`, `msgpack` or `cbor`",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Encoding::Json => "json",
+            Encoding::MessagePack => "msgpack",
+            Encoding::Cbor => "cbor",
+        })
+    }
+}
@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
+use std::time::Duration;
+
+/// JSON-RPC 2.0 error object, see https://www.jsonrpc.org/specification#error_object
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    pub const PARSE_ERROR: i64 = -32700;
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    pub const INTERNAL_ERROR: i64 = -32603;
+    /// The request was cancelled by `$/cancelRequest` (same code as LSP).
+    pub const REQUEST_CANCELLED: i64 = -32800;
+    /// The request did not finish within its timeout.
+    pub const REQUEST_TIMEOUT: i64 = -32001;
+    /// Rejected by the authentication middleware.
+    pub const UNAUTHORIZED: i64 = -32002;
+    /// Rejected by the rate-limiting middleware.
+    pub const RATE_LIMITED: i64 = -32003;
+    /// Replay mode has no recorded response for the request.
+    pub const NOT_RECORDED: i64 = -32004;
+    /// Client side: the connection failed or closed before a response arrived.
+    pub const TRANSPORT_ERROR: i64 = -32000;
+
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    pub fn parse_error(detail: impl fmt::Display) -> Self {
+        RpcError::new(Self::PARSE_ERROR, "Parse error").with_data(Value::String(detail.to_string()))
+    }
+
+    pub fn invalid_request(detail: impl fmt::Display) -> Self {
+        RpcError::new(Self::INVALID_REQUEST, "Invalid Request")
+            .with_data(Value::String(detail.to_string()))
+    }
+
+    pub fn method_not_found(method: &str) -> Self {
+        RpcError::new(Self::METHOD_NOT_FOUND, "Method not found")
+            .with_data(Value::String(method.to_string()))
+    }
+
+    pub fn invalid_params(detail: impl fmt::Display) -> Self {
+        RpcError::new(Self::INVALID_PARAMS, "Invalid params")
+            .with_data(Value::String(detail.to_string()))
+    }
+
+    pub fn internal_error(detail: impl fmt::Display) -> Self {
+        RpcError::new(Self::INTERNAL_ERROR, "Internal error")
+            .with_data(Value::String(detail.to_string()))
+    }
+
+    pub fn request_cancelled() -> Self {
+        RpcError::new(Self::REQUEST_CANCELLED, "Request cancelled")
+    }
+
+    pub fn timeout(timeout: Duration) -> Self {
+        RpcError::new(Self::REQUEST_TIMEOUT, "Request timed out")
+            .with_data(json!({"timeout_ms": timeout.as_millis() as u64}))
+    }
+
+    /// A request arrived after `shutdown`, or while the server drains.
+    pub fn shutting_down() -> Self {
+        RpcError::new(Self::INVALID_REQUEST, "Server is shutting down")
+    }
+
+    pub fn unauthorized() -> Self {
+        RpcError::new(Self::UNAUTHORIZED, "Unauthorized")
+    }
+
+    pub fn rate_limited() -> Self {
+        RpcError::new(Self::RATE_LIMITED, "Rate limit exceeded")
+    }
+
+    pub fn not_recorded(method: &str, params: Option<&Value>) -> Self {
+        RpcError::new(Self::NOT_RECORDED, "No recorded response")
+            .with_data(json!({"method": method, "params": params}))
+    }
+
+    pub fn transport(detail: impl fmt::Display) -> Self {
+        RpcError::new(Self::TRANSPORT_ERROR, "Transport error")
+            .with_data(Value::String(detail.to_string()))
+    }
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)?;
+        if let Some(data) = &self.data {
+            write!(f, ": {}", data)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RpcError {}
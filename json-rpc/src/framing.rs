@@ -0,0 +1,328 @@
+use crate::encoding::Encoding;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+use std::str::FromStr;
+
+/// Longest accepted header line for [`ContentLengthFramer`] and the HTTP
+/// transport.
+pub(crate) const MAX_HEADER_LINE: usize = 8 * 1024;
+
+/// A message larger than the reader's limit. It has been skipped, so the
+/// stream can still be read; see [`Framer::read_limited_frame`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameTooLarge {
+    pub limit: usize,
+}
+
+impl FrameTooLarge {
+    /// Returns the [`FrameTooLarge`] inside `err`, if that is what it is.
+    pub fn find(err: &io::Error) -> Option<&FrameTooLarge> {
+        err.get_ref()?.downcast_ref()
+    }
+}
+
+impl fmt::Display for FrameTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "message exceeds {} bytes", self.limit)
+    }
+}
+
+impl Error for FrameTooLarge {}
+
+impl From<FrameTooLarge> for io::Error {
+    fn from(err: FrameTooLarge) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// Appends bytes up to the next `\n` (consumed, not stored) to `line`.
+/// Bytes beyond `limit` are consumed but dropped. Returns the number of
+/// bytes consumed and whether the line was cut off.
+pub(crate) fn read_line_limited(
+    reader: &mut dyn BufRead,
+    line: &mut Vec<u8>,
+    limit: usize,
+) -> io::Result<(usize, bool)> {
+    let (mut consumed, mut truncated) = (0, false);
+    loop {
+        let available = match reader.fill_buf() {
+            Ok(available) => available,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        if available.is_empty() {
+            return Ok((consumed, truncated));
+        }
+        let (chunk, done) = match available.iter().position(|&byte| byte == b'\n') {
+            Some(end) => (&available[..end], true),
+            None => (available, false),
+        };
+        let room = limit.saturating_sub(line.len());
+        if chunk.len() > room {
+            truncated = true;
+        }
+        line.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        let used = chunk.len() + usize::from(done);
+        reader.consume(used);
+        consumed += used;
+        if done {
+            return Ok((consumed, truncated));
+        }
+    }
+}
+
+/// 消息分帧：把字节流切分成一条条完整的消息。
+pub trait Framer: Send + Sync {
+    /// Reads the next message, returning `None` on a clean end of stream.
+    fn read_frame(&self, reader: &mut dyn BufRead) -> io::Result<Option<Vec<u8>>>;
+
+    /// Like [`Framer::read_typed_frame`], but a message longer than
+    /// `max_size` bytes fails with a [`FrameTooLarge`] error. The built-in
+    /// framers skip such a message without buffering it, so reading can go
+    /// on with the next one.
+    fn read_limited_frame(
+        &self,
+        reader: &mut dyn BufRead,
+        max_size: usize,
+    ) -> io::Result<Option<(Vec<u8>, Option<String>)>> {
+        match self.read_typed_frame(reader)? {
+            Some((payload, _)) if payload.len() > max_size => {
+                Err(FrameTooLarge { limit: max_size }.into())
+            }
+            frame => Ok(frame),
+        }
+    }
+
+    fn write_frame(&self, writer: &mut dyn Write, payload: &[u8]) -> io::Result<()>;
+
+    /// Like [`Framer::read_frame`], also returning the message's
+    /// `Content-Type` if the framing carries one.
+    fn read_typed_frame(
+        &self,
+        reader: &mut dyn BufRead,
+    ) -> io::Result<Option<(Vec<u8>, Option<String>)>> {
+        Ok(self.read_frame(reader)?.map(|payload| (payload, None)))
+    }
+
+    /// Like [`Framer::write_frame`], labelling the payload with
+    /// `content_type` if the framing can carry it.
+    fn write_typed_frame(
+        &self,
+        writer: &mut dyn Write,
+        payload: &[u8],
+        _content_type: &str,
+    ) -> io::Result<()> {
+        self.write_frame(writer, payload)
+    }
+}
+
+/// One JSON document per line. Payloads must not contain raw newlines.
+pub struct NewlineFramer;
+
+impl Framer for NewlineFramer {
+    fn read_frame(&self, reader: &mut dyn BufRead) -> io::Result<Option<Vec<u8>>> {
+        Ok(self
+            .read_limited_frame(reader, usize::MAX)?
+            .map(|(payload, _)| payload))
+    }
+
+    fn read_limited_frame(
+        &self,
+        reader: &mut dyn BufRead,
+        max_size: usize,
+    ) -> io::Result<Option<(Vec<u8>, Option<String>)>> {
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            // 多读一个字节，以便容纳行尾的 \r
+            let (consumed, truncated) =
+                read_line_limited(reader, &mut line, max_size.saturating_add(1))?;
+            if consumed == 0 {
+                return Ok(None);
+            }
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            if truncated || line.len() > max_size {
+                return Err(FrameTooLarge { limit: max_size }.into());
+            }
+            if !line.iter().all(u8::is_ascii_whitespace) {
+                return Ok(Some((line, None)));
+            }
+        }
+    }
+
+    fn write_frame(&self, writer: &mut dyn Write, payload: &[u8]) -> io::Result<()> {
+        if payload.contains(&b'\n') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "newline-delimited payload contains a newline",
+            ));
+        }
+        write_whole_frame(writer, &[payload, b"\n"])
+    }
+
+    fn write_typed_frame(
+        &self,
+        writer: &mut dyn Write,
+        payload: &[u8],
+        content_type: &str,
+    ) -> io::Result<()> {
+        if Encoding::from_content_type(content_type) != Some(Encoding::Json) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("newline framing cannot carry {}", content_type),
+            ));
+        }
+        self.write_frame(writer, payload)
+    }
+}
+
+/// Writes a frame with a single `write_all`, so a small frame is
+/// not split into packets that Nagle's algorithm holds back.
+fn write_whole_frame(writer: &mut dyn Write, parts: &[&[u8]]) -> io::Result<()> {
+    writer.write_all(&parts.concat())?;
+    writer.flush()
+}
+
+/// `Content-Length: N\r\n\r\n` header framing as used by the Language Server Protocol.
+pub struct ContentLengthFramer;
+
+impl Framer for ContentLengthFramer {
+    fn read_frame(&self, reader: &mut dyn BufRead) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.read_typed_frame(reader)?.map(|(payload, _)| payload))
+    }
+
+    fn write_frame(&self, writer: &mut dyn Write, payload: &[u8]) -> io::Result<()> {
+        let header = format!("Content-Length: {}\r\n\r\n", payload.len());
+        write_whole_frame(writer, &[header.as_bytes(), payload])
+    }
+
+    fn read_typed_frame(
+        &self,
+        reader: &mut dyn BufRead,
+    ) -> io::Result<Option<(Vec<u8>, Option<String>)>> {
+        self.read_limited_frame(reader, usize::MAX)
+    }
+
+    fn read_limited_frame(
+        &self,
+        reader: &mut dyn BufRead,
+        max_size: usize,
+    ) -> io::Result<Option<(Vec<u8>, Option<String>)>> {
+        let mut content_length = None;
+        let mut content_type = None;
+        let mut seen_header = false;
+        loop {
+            let mut line = Vec::new();
+            let (consumed, truncated) = read_line_limited(reader, &mut line, MAX_HEADER_LINE)?;
+            if consumed == 0 {
+                if seen_header {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                return Ok(None);
+            }
+            if truncated {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "header line too long",
+                ));
+            }
+            let line = String::from_utf8(line).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "header is not valid UTF-8")
+            })?;
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() {
+                if !seen_header {
+                    // 容忍消息之间多余的空行
+                    continue;
+                }
+                break;
+            }
+            seen_header = true;
+            let (name, value) = line.split_once(':').ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("bad header: {}", line))
+            })?;
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                let length = value.trim().parse::<usize>().map_err(|err| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("bad Content-Length: {}", err),
+                    )
+                })?;
+                content_length = Some(length);
+            } else if name.trim().eq_ignore_ascii_case("content-type") {
+                content_type = Some(value.trim().to_string());
+            }
+        }
+        let length = content_length.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header")
+        })?;
+        if length > max_size {
+            // 跳过消息体，下一条消息仍然可以正常读取
+            let skipped = io::copy(&mut reader.take(length as u64), &mut io::sink())?;
+            if skipped < length as u64 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            return Err(FrameTooLarge { limit: max_size }.into());
+        }
+        let mut payload = vec![0; length];
+        reader.read_exact(&mut payload)?;
+        Ok(Some((payload, content_type)))
+    }
+
+    fn write_typed_frame(
+        &self,
+        writer: &mut dyn Write,
+        payload: &[u8],
+        content_type: &str,
+    ) -> io::Result<()> {
+        let header = format!(
+            "Content-Length: {}\r\nContent-Type: {}\r\n\r\n",
+            payload.len(),
+            content_type
+        );
+        write_whole_frame(writer, &[header.as_bytes(), payload])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Framing {
+    #[default]
+    Newline,
+    ContentLength,
+}
+
+impl Framing {
+    pub fn framer(self) -> Box<dyn Framer> {
+        match self {
+            Framing::Newline => Box::new(NewlineFramer),
+            Framing::ContentLength => Box::new(ContentLengthFramer),
+        }
+    }
+}
+
+impl FromStr for Framing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "newline" | "line" => Ok(Framing::Newline),
+            "content-length" | "lsp" => Ok(Framing::ContentLength),
+            _ => Err(format!(
+                "unknown framing `{}`, expected `newline` or `content-length`",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Framing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Framing::Newline => write!(f, "newline"),
+            Framing::ContentLength => write!(f, "content-length"),
+        }
+    }
+}
@@ -0,0 +1,32 @@
+mod calls;
+pub mod client;
+pub mod discover;
+pub mod encoding;
+pub mod error;
+pub mod framing;
+pub mod logger;
+pub mod message;
+pub mod middleware;
+pub mod pool;
+pub mod pubsub;
+pub mod record;
+pub mod router;
+pub mod server;
+pub mod session;
+pub mod timer;
+pub mod tls;
+pub mod tools;
+pub mod transport;
+
+pub use client::Client;
+pub use encoding::Encoding;
+pub use error::RpcError;
+pub use framing::Framing;
+pub use message::{Request, Response};
+pub use router::Router;
+pub use server::Server;
+pub use session::{Context, Session};
+
+pub use json_rpc_derive::rpc;
+pub use schemars;
+pub use serde;
@@ -0,0 +1,82 @@
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 日志只写到 stderr 或文件，stdout 专门留给协议消息。
+enum Target {
+    Stderr,
+    File(Mutex<File>),
+}
+
+struct Logger {
+    level: LevelFilter,
+    target: Target,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or_default();
+        let line = format!(
+            "{:.3} {:<5} {}: {}\n",
+            timestamp,
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        // 写日志失败时没有更好的去处，直接忽略
+        let _ = match &self.target {
+            Target::Stderr => io::stderr().lock().write_all(line.as_bytes()),
+            Target::File(file) => match file.lock() {
+                Ok(mut file) => file.write_all(line.as_bytes()),
+                Err(_) => Ok(()),
+            },
+        };
+    }
+
+    fn flush(&self) {
+        if let Target::File(file) = &self.target {
+            if let Ok(mut file) = file.lock() {
+                let _ = file.flush();
+            }
+        }
+    }
+}
+
+/// Installs the global logger, appending to `path` when given, otherwise
+/// writing to stderr.
+pub fn init(level: LevelFilter, path: Option<&Path>) -> io::Result<()> {
+    let target = match path {
+        Some(path) => Target::File(Mutex::new(
+            OpenOptions::new().create(true).append(true).open(path)?,
+        )),
+        None => Target::Stderr,
+    };
+    log::set_boxed_logger(Box::new(Logger { level, target }))
+        .map_err(|err| io::Error::new(io::ErrorKind::AlreadyExists, err))?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+/// Reads the level from `RPC_LOG` (e.g. `debug`) and the optional log file
+/// from `RPC_LOG_FILE`. Defaults to `warn` on stderr.
+pub fn init_from_env() -> io::Result<()> {
+    let level = std::env::var("RPC_LOG")
+        .ok()
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(LevelFilter::Warn);
+    let path = std::env::var_os("RPC_LOG_FILE");
+    init(level, path.as_deref().map(Path::new))
+}
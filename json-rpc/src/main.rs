@@ -1,29 +1,300 @@
+use clap::Parser;
+use json_rpc::middleware::{Auth, Metrics, RateLimit, RequestLog};
+use json_rpc::pubsub::Hub;
+use json_rpc::record::{Recorder, Replay};
+use json_rpc::schemars::JsonSchema;
+use json_rpc::server::{Limits, SessionEnd};
+use json_rpc::tools::Tools;
+use json_rpc::{logger, tls, transport, Context, Framing, Router, RpcError, Server};
+use log::{error, info, warn};
+use serde::Deserialize;
 use serde_json::{json, Value};
-use std::io::{self, BufRead, Write};
+use std::io;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
-fn handle_request(request: &Value) -> Value {
-    let method = request["method"].as_str();
-    let params = &request["params"];
-    println!("Params: {}", params);
-    match method {
-        Some("echo") => params.clone(),
-        _ => json!({"error": "Method not found"}),
-    }
+#[derive(Parser)]
+#[command(about = "JSON-RPC 2.0 server")]
+struct Cli {
+    /// Message framing: `newline` or `content-length`
+    #[arg(long, default_value_t = Framing::Newline)]
+    framing: Framing,
+
+    /// Listen for TCP connections on this address, e.g. 127.0.0.1:4000
+    #[arg(long, value_name = "ADDR")]
+    tcp: Vec<String>,
+
+    /// Serve `--tcp` listeners over TLS with this PEM certificate chain
+    #[arg(long, value_name = "PATH", requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM private key for `--tls-cert`
+    #[arg(long, value_name = "PATH", requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+
+    /// Require TLS clients to present a certificate signed by a CA in this
+    /// PEM file (mutual TLS)
+    #[arg(long, value_name = "PATH", requires = "tls_cert")]
+    tls_client_ca: Option<PathBuf>,
+
+    /// Listen for connections on this Unix domain socket
+    #[cfg(unix)]
+    #[arg(long, value_name = "PATH")]
+    unix: Vec<PathBuf>,
+
+    /// Serve JSON-RPC over HTTP (`POST /rpc`) on this address
+    #[arg(long, value_name = "ADDR")]
+    http: Vec<String>,
+
+    /// Serve JSON-RPC over WebSocket on this address
+    #[arg(long, value_name = "ADDR")]
+    ws: Vec<String>,
+
+    /// Maximum number of requests handled at the same time
+    #[arg(long, value_name = "N")]
+    workers: Option<usize>,
+
+    /// Fail requests that take longer than this many milliseconds
+    #[arg(long, value_name = "MS")]
+    timeout: Option<u64>,
+
+    /// Require `meta.token` to be one of these tokens (repeatable)
+    #[arg(long, value_name = "TOKEN")]
+    token: Vec<String>,
+
+    /// Reject messages larger than this many bytes
+    #[arg(long, value_name = "BYTES", default_value_t = Limits::default().max_message_size)]
+    max_message_size: usize,
+
+    /// Reject messages whose arrays and objects nest deeper than this
+    #[arg(long, value_name = "N", default_value_t = Limits::default().max_depth)]
+    max_depth: usize,
+
+    /// Allow each connection this many requests per second
+    #[arg(long, value_name = "N")]
+    rate_limit: Option<u32>,
+
+    /// Keep the tasks behind `todo.*` in this JSON file
+    #[arg(long, value_name = "PATH")]
+    todo_file: Option<PathBuf>,
+
+    /// Record every request and its response to this JSONL file
+    #[arg(long, value_name = "PATH", conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Answer requests from a recording made with `--record`
+    #[arg(long, value_name = "PATH")]
+    replay: Option<PathBuf>,
+
+    /// Also serve stdin/stdout (the default when no listener is given)
+    #[arg(long)]
+    stdio: bool,
+
+    /// On SIGINT/SIGTERM, wait this many milliseconds for in-flight requests
+    #[arg(long, value_name = "MS", default_value_t = 5000)]
+    grace: u64,
 }
 
-fn main() {
-    let stdin = io::stdin();
-    let mut stdout = io::stdout();
-
-    for line in stdin.lock().lines() {
-        if let Ok(line) = line {
-            if let Ok(request) = serde_json::from_str::<Value>(&line) {
-                let response = handle_request(&request);
-                if let Ok(response_str) = serde_json::to_string(&response) {
-                    writeln!(stdout, "{}", response_str).unwrap();
-                    stdout.flush().unwrap();
+#[derive(Deserialize, JsonSchema)]
+struct AddParams {
+    a: i64,
+    b: i64,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct AskParams {
+    question: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct CountdownParams {
+    from: u32,
+    #[serde(default = "default_interval")]
+    interval_ms: u64,
+}
+
+fn default_interval() -> u64 {
+    100
+}
+
+fn build_router(hub: &Arc<Hub>, tools: &Arc<Tools>) -> Router {
+    let mut router = Router::new();
+    hub.register(&mut router);
+    tools.register(&mut router);
+    router
+        .register("echo", |params: Value| Ok::<_, RpcError>(params))
+        .register("add", |p: AddParams| {
+            p.a.checked_add(p.b)
+                .ok_or_else(|| RpcError::invalid_params("integer overflow"))
+        })
+        .register_with_context("ask", |ctx: &Context, p: AskParams| {
+            // 反过来调用客户端，等它回答
+            ctx.session()
+                .call::<_, bool>("confirm", json!({"message": p.question}))
+        })
+        .register_with_context("countdown", |ctx: &Context, p: CountdownParams| {
+            for remaining in (1..=p.from).rev() {
+                if ctx.is_cancelled() {
+                    return Err(RpcError::request_cancelled());
                 }
+                ctx.progress(remaining);
+                thread::sleep(Duration::from_millis(p.interval_ms));
             }
+            Ok("liftoff")
+        })
+        .describe("echo", "Returns its params unchanged.")
+        .describe("add", "Adds two integers.")
+        .describe(
+            "ask",
+            "Asks the client to confirm `question` and returns its answer.",
+        )
+        .describe(
+            "countdown",
+            "Counts down from `from`, reporting each step as `$/progress`.",
+        );
+    router
+}
+
+/// The first SIGINT/SIGTERM drains the server and exits with 0, or 1 if the
+/// grace period runs out; a second one exits immediately.
+fn handle_signals(server: Server, grace: Duration) -> io::Result<()> {
+    let signalled = AtomicBool::new(false);
+    ctrlc::set_handler(move || {
+        if signalled.swap(true, Ordering::SeqCst) {
+            warn!("second signal, exiting immediately");
+            process::exit(1);
+        }
+        info!("signal received, shutting down");
+        // 处理函数所在的线程要继续接收第二个信号
+        let server = server.clone();
+        thread::spawn(move || {
+            let drained = server.shutdown(grace);
+            process::exit(if drained { 0 } else { 1 });
+        });
+    })
+    .map_err(io::Error::other)
+}
+
+/// Returns the process exit status.
+fn run(cli: Cli) -> io::Result<i32> {
+    let hub = Hub::new();
+    let tools = Tools::new(cli.todo_file.clone())?;
+    let mut router = build_router(&hub, &tools);
+    if let Some(timeout) = cli.timeout {
+        router.default_timeout(Duration::from_millis(timeout));
+    }
+    let metrics = Metrics::new();
+    metrics.register(&mut router);
+    // 认证放在最外层：未认证的请求不计入统计，也不消耗限流配额
+    if !cli.token.is_empty() {
+        router.layer(Auth::new(cli.token.iter().cloned()));
+    }
+    router.layer(RequestLog).layer(Arc::clone(&metrics));
+    if let Some(rate) = cli.rate_limit {
+        router.layer(RateLimit::new(f64::from(rate), rate));
+    }
+    if let Some(path) = &cli.record {
+        router.layer(Recorder::create(path)?);
+    }
+    if let Some(path) = &cli.replay {
+        router.layer(Replay::load(path)?);
+    }
+    let server = match cli.workers {
+        Some(workers) => Server::with_workers(router, workers),
+        None => Server::new(router),
+    }
+    .with_limits(Limits {
+        max_message_size: cli.max_message_size,
+        max_depth: cli.max_depth,
+    });
+    info!("handling up to {} requests concurrently", server.workers());
+    handle_signals(server.clone(), Duration::from_millis(cli.grace))?;
+    let tls = match (&cli.tls_cert, &cli.tls_key) {
+        (Some(cert), Some(key)) => {
+            Some(tls::server_config(cert, key, cli.tls_client_ca.as_deref())?)
+        }
+        _ => None,
+    };
+    let mut listeners = Vec::new();
+
+    for addr in &cli.tcp {
+        let listener = TcpListener::bind(addr)?;
+        let server = server.clone();
+        let framing = cli.framing;
+        match tls.clone() {
+            Some(config) => {
+                info!("listening on tls {}", listener.local_addr()?);
+                listeners.push(thread::spawn(move || {
+                    transport::tcp::serve_tls(listener, server, framing, config)
+                }));
+            }
+            None => {
+                info!("listening on tcp {}", listener.local_addr()?);
+                listeners.push(thread::spawn(move || {
+                    transport::tcp::serve(listener, server, framing)
+                }));
+            }
+        }
+    }
+    for addr in &cli.http {
+        let listener = TcpListener::bind(addr)?;
+        info!("listening on http://{}/rpc", listener.local_addr()?);
+        let server = server.clone();
+        listeners.push(thread::spawn(move || {
+            transport::http::serve(listener, server)
+        }));
+    }
+    for addr in &cli.ws {
+        let listener = TcpListener::bind(addr)?;
+        info!("listening on ws://{}", listener.local_addr()?);
+        let server = server.clone();
+        listeners.push(thread::spawn(move || {
+            transport::websocket::serve(listener, server)
+        }));
+    }
+    #[cfg(unix)]
+    for path in &cli.unix {
+        let listener = transport::unix::bind(path)?;
+        info!("listening on unix {}", path.display());
+        let server = server.clone();
+        let framing = cli.framing;
+        listeners.push(thread::spawn(move || {
+            transport::unix::serve(listener, server, framing)
+        }));
+    }
+
+    if cli.stdio || listeners.is_empty() {
+        let stdin = io::stdin();
+        let end = server.serve(&*cli.framing.framer(), stdin.lock(), io::stdout())?;
+        // 与 LSP 一致：没有先收到 shutdown 就 exit 时以 1 退出
+        return Ok(match end {
+            SessionEnd::Exit { shutdown: false } => 1,
+            _ => 0,
+        });
+    }
+    for listener in listeners {
+        listener
+            .join()
+            .map_err(|_| io::Error::other("listener thread panicked"))??;
+    }
+    Ok(0)
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(err) = logger::init_from_env() {
+        eprintln!("Failed to initialise logging: {}", err);
+    }
+    match run(cli) {
+        Ok(status) => process::exit(status),
+        Err(err) => {
+            error!("server stopped: {}", err);
+            process::exit(1);
         }
     }
 }
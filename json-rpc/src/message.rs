@@ -0,0 +1,120 @@
+use crate::error::RpcError;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+pub const VERSION: &str = "2.0";
+
+/// A request, or a notification when `id` is absent. An explicit
+/// `"id": null` is a request like any other: it is `Some(Value::Null)` and
+/// gets a response.
+///
+/// `meta` is an extension member for out-of-band data such as an auth
+/// token: `{"meta": {"token": "..."}}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Request {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub id: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Value>,
+}
+
+/// Only called for members that are present, so `null` becomes
+/// `Some(Value::Null)` while a missing member stays `None`.
+fn present<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(deserializer).map(Some)
+}
+
+impl Request {
+    pub fn new(method: &str, params: Option<Value>, id: Option<Value>) -> Self {
+        Request {
+            jsonrpc: VERSION.to_string(),
+            method: method.to_string(),
+            params,
+            id,
+            meta: None,
+        }
+    }
+
+    pub fn meta(&self, key: &str) -> Option<&Value> {
+        self.meta.as_ref().and_then(|meta| meta.get(key))
+    }
+
+    pub fn is_notification(&self) -> bool {
+        self.id.is_none()
+    }
+}
+
+/// Whether `message` deserializes as a [`Request`]: only `jsonrpc` and
+/// `method` are required, and both must be strings.
+pub(crate) fn is_well_formed(message: &Value) -> bool {
+    message.get("jsonrpc").is_some_and(Value::is_string)
+        && message.get("method").is_some_and(Value::is_string)
+}
+
+/// How deeply arrays and objects nest in `value`; a scalar has depth 0.
+pub fn nesting_depth(value: &Value) -> usize {
+    // 用显式的栈，过深的输入不会耗尽调用栈
+    let mut deepest = 0;
+    let mut stack = vec![(value, 0)];
+    while let Some((value, depth)) = stack.pop() {
+        deepest = deepest.max(depth);
+        match value {
+            Value::Array(items) => stack.extend(items.iter().map(|item| (item, depth + 1))),
+            Value::Object(members) => stack.extend(members.values().map(|item| (item, depth + 1))),
+            _ => {}
+        }
+    }
+    deepest
+}
+
+/// True for a response object, as opposed to a request or notification;
+/// lets either peer tell apart the messages it receives.
+pub fn is_response(message: &Value) -> bool {
+    message.get("method").is_none()
+        && (message.get("result").is_some() || message.get("error").is_some())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    pub jsonrpc: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+    pub id: Value,
+}
+
+impl Response {
+    pub fn success(id: Value, result: Value) -> Self {
+        Response {
+            jsonrpc: VERSION.to_string(),
+            result: Some(result),
+            error: None,
+            id,
+        }
+    }
+
+    pub fn failure(id: Value, error: RpcError) -> Self {
+        Response {
+            jsonrpc: VERSION.to_string(),
+            result: None,
+            error: Some(error),
+            id,
+        }
+    }
+
+    pub fn into_result(self) -> Result<Value, RpcError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.result.unwrap_or(Value::Null)),
+        }
+    }
+}
@@ -0,0 +1,228 @@
+//! 中间件：在每次方法调用前后插入认证、日志、限流、统计等通用逻辑。
+
+use crate::error::RpcError;
+use crate::message::Request;
+use crate::router::Router;
+use crate::session::Context;
+use log::info;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A layer around method dispatch. Call `next.run(ctx, request)` to continue
+/// down the chain, or return an error to short-circuit it.
+pub trait Middleware: Send + Sync {
+    fn handle(&self, ctx: &Context, request: &Request, next: Next<'_>) -> Result<Value, RpcError>;
+}
+
+impl<F> Middleware for F
+where
+    F: Fn(&Context, &Request, Next<'_>) -> Result<Value, RpcError> + Send + Sync,
+{
+    fn handle(&self, ctx: &Context, request: &Request, next: Next<'_>) -> Result<Value, RpcError> {
+        self(ctx, request, next)
+    }
+}
+
+/// The rest of the chain, ending in the method handler.
+pub struct Next<'a> {
+    router: &'a Router,
+    layers: &'a [Arc<dyn Middleware>],
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(router: &'a Router, layers: &'a [Arc<dyn Middleware>]) -> Self {
+        Next { router, layers }
+    }
+
+    /// See [`Router::handles`].
+    pub fn handles(&self, method: &str) -> bool {
+        self.router.handles(method)
+    }
+
+    pub fn run(self, ctx: &Context, request: &Request) -> Result<Value, RpcError> {
+        match self.layers.split_first() {
+            Some((layer, rest)) => layer.handle(ctx, request, Next::new(self.router, rest)),
+            None => self
+                .router
+                .call(ctx, &request.method, request.params.clone()),
+        }
+    }
+}
+
+/// Rejects requests whose `meta.token` is not one of the accepted tokens.
+pub struct Auth {
+    tokens: HashSet<String>,
+    public: HashSet<String>,
+}
+
+impl Auth {
+    pub fn new<I, S>(tokens: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Auth {
+            tokens: tokens.into_iter().map(Into::into).collect(),
+            public: HashSet::new(),
+        }
+    }
+
+    /// Lets `method` through without a token.
+    pub fn allow(mut self, method: &str) -> Self {
+        self.public.insert(method.to_string());
+        self
+    }
+}
+
+impl Middleware for Auth {
+    fn handle(&self, ctx: &Context, request: &Request, next: Next<'_>) -> Result<Value, RpcError> {
+        let token = request.meta("token").and_then(Value::as_str);
+        let allowed = self.public.contains(&request.method)
+            || token.is_some_and(|token| self.tokens.contains(token));
+        if !allowed {
+            return Err(RpcError::unauthorized());
+        }
+        next.run(ctx, request)
+    }
+}
+
+/// Logs one structured line per call at `info` level: method, id, session,
+/// duration and outcome.
+pub struct RequestLog;
+
+impl Middleware for RequestLog {
+    fn handle(&self, ctx: &Context, request: &Request, next: Next<'_>) -> Result<Value, RpcError> {
+        let started = Instant::now();
+        let result = next.run(ctx, request);
+        let mut entry = json!({
+            "method": request.method,
+            "id": ctx.id(),
+            "session": ctx.session().id(),
+            "duration_ms": started.elapsed().as_secs_f64() * 1000.0,
+        });
+        match &result {
+            Ok(_) => entry["ok"] = json!(true),
+            Err(err) => {
+                entry["ok"] = json!(false);
+                entry["error"] = json!(err.code);
+            }
+        }
+        info!(target: "json_rpc::request", "{}", entry);
+        result
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket per connection, keyed by
+/// [`Session::id`](crate::session::Session::id): `rate` calls per second
+/// with bursts of up to `burst` calls.
+pub struct RateLimit {
+    rate: f64,
+    burst: f64,
+    buckets: Mutex<HashMap<u64, Bucket>>,
+}
+
+/// Buckets untouched for this long are dropped, so closed sessions don't
+/// accumulate.
+const IDLE_BUCKET: Duration = Duration::from_secs(60);
+
+impl RateLimit {
+    pub fn new(rate: f64, burst: u32) -> Self {
+        RateLimit {
+            rate,
+            burst: f64::from(burst.max(1)),
+            buckets: Mutex::default(),
+        }
+    }
+
+    fn acquire(&self, session: u64) -> bool {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        buckets.retain(|id, bucket| *id == session || now - bucket.updated < IDLE_BUCKET);
+        let bucket = buckets.entry(session).or_insert(Bucket {
+            tokens: self.burst,
+            updated: now,
+        });
+        let elapsed = (now - bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl Middleware for RateLimit {
+    fn handle(&self, ctx: &Context, request: &Request, next: Next<'_>) -> Result<Value, RpcError> {
+        if !self.acquire(ctx.session().id()) {
+            return Err(RpcError::rate_limited());
+        }
+        next.run(ctx, request)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct MethodStats {
+    pub calls: u64,
+    pub errors: u64,
+    pub total_ms: f64,
+}
+
+/// Counts calls, errors and time spent per method. Calls to methods the
+/// router doesn't handle all go to one [`Metrics::UNKNOWN`] entry, so that
+/// made-up method names can't grow the table.
+#[derive(Default)]
+pub struct Metrics {
+    methods: Mutex<BTreeMap<String, MethodStats>>,
+}
+
+impl Metrics {
+    pub const UNKNOWN: &'static str = "<unknown>";
+
+    pub fn new() -> Arc<Self> {
+        Arc::new(Metrics::default())
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<String, MethodStats> {
+        self.methods.lock().unwrap().clone()
+    }
+
+    /// Registers `rpc.metrics`, which returns [`Metrics::snapshot`].
+    pub fn register(self: &Arc<Self>, router: &mut Router) {
+        let metrics = Arc::clone(self);
+        router
+            .register("rpc.metrics", move |(): ()| {
+                Ok::<_, RpcError>(metrics.snapshot())
+            })
+            .describe("rpc.metrics", "Call, error and timing counters per method.");
+    }
+}
+
+impl Middleware for Arc<Metrics> {
+    fn handle(&self, ctx: &Context, request: &Request, next: Next<'_>) -> Result<Value, RpcError> {
+        let method = if next.handles(&request.method) {
+            request.method.as_str()
+        } else {
+            Metrics::UNKNOWN
+        };
+        let started = Instant::now();
+        let result = next.run(ctx, request);
+        let mut methods = self.methods.lock().unwrap();
+        let stats = methods.entry(method.to_string()).or_default();
+        stats.calls += 1;
+        stats.errors += u64::from(result.is_err());
+        stats.total_ms += started.elapsed().as_secs_f64() * 1000.0;
+        result
+    }
+}
@@ -0,0 +1,55 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// 固定数量的工作线程，限制同时执行的请求数。
+pub struct WorkerPool {
+    jobs: Mutex<Sender<Job>>,
+    size: usize,
+}
+
+impl WorkerPool {
+    pub fn new(size: usize) -> Self {
+        let size = size.max(1);
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let queue = Arc::new(Mutex::new(queue));
+        for index in 0..size {
+            let queue = Arc::clone(&queue);
+            thread::Builder::new()
+                .name(format!("rpc-worker-{}", index))
+                .spawn(move || loop {
+                    let job = match queue.lock().unwrap().recv() {
+                        Ok(job) => job,
+                        Err(_) => break,
+                    };
+                    // 任务 panic 时工作线程继续运行；调用方由任务自己的析构负责回复
+                    let _ = panic::catch_unwind(AssertUnwindSafe(job));
+                })
+                .expect("failed to spawn worker thread");
+        }
+        WorkerPool {
+            jobs: Mutex::new(jobs),
+            size,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Queues `job`; it runs as soon as a worker is free.
+    pub fn execute<F: FnOnce() + Send + 'static>(&self, job: F) {
+        // 工作线程只会在发送端销毁后退出，这里发送不会失败
+        let _ = self.jobs.lock().unwrap().send(Box::new(job));
+    }
+}
+
+impl Default for WorkerPool {
+    fn default() -> Self {
+        let size = thread::available_parallelism().map_or(4, |n| n.get());
+        WorkerPool::new(size)
+    }
+}
@@ -0,0 +1,120 @@
+//! 订阅/发布：客户端通过 `subscribe` 订阅主题，服务端向订阅者推送通知。
+
+use crate::error::RpcError;
+use crate::router::Router;
+use crate::session::{Context, Session};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+
+struct Subscription {
+    topic: String,
+    session: Weak<Session>,
+}
+
+/// Tracks subscriptions across all sessions.
+///
+/// Subscribers receive `{"method": <topic>, "params": {"subscription": <id>,
+/// "result": <data>}}` notifications. Sessions are held weakly, so closed
+/// connections drop out on the next publish.
+#[derive(Default)]
+pub struct Hub {
+    next_id: AtomicU64,
+    subscriptions: Mutex<HashMap<u64, Subscription>>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct SubscribeParams {
+    topic: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct UnsubscribeParams {
+    subscription: u64,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct PublishParams {
+    topic: String,
+    #[serde(default)]
+    data: Value,
+}
+
+impl Hub {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Hub::default())
+    }
+
+    pub fn subscribe(&self, session: &Arc<Session>, topic: &str) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let subscription = Subscription {
+            topic: topic.to_string(),
+            session: Arc::downgrade(session),
+        };
+        self.subscriptions.lock().unwrap().insert(id, subscription);
+        id
+    }
+
+    /// Only the session that created a subscription may cancel it.
+    pub fn unsubscribe(&self, session: &Arc<Session>, id: u64) -> bool {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        let owned = subscriptions
+            .get(&id)
+            .and_then(|subscription| subscription.session.upgrade())
+            .is_some_and(|owner| owner.id() == session.id());
+        owned && subscriptions.remove(&id).is_some()
+    }
+
+    /// Pushes `data` to every subscriber of `topic`, returning how many
+    /// sessions it was delivered to.
+    pub fn publish(&self, topic: &str, data: &Value) -> usize {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        let mut delivered = 0;
+        subscriptions.retain(|id, subscription| {
+            if subscription.topic != topic {
+                return true;
+            }
+            let Some(session) = subscription.session.upgrade() else {
+                return false;
+            };
+            let sent = session.notify(topic, json!({"subscription": id, "result": data}));
+            if sent {
+                delivered += 1;
+            }
+            sent
+        });
+        delivered
+    }
+
+    /// Registers `subscribe`, `unsubscribe` and `publish` on `router`.
+    pub fn register(self: &Arc<Self>, router: &mut Router) {
+        let hub = Arc::clone(self);
+        router.register_with_context("subscribe", move |ctx: &Context, p: SubscribeParams| {
+            Ok::<_, RpcError>(hub.subscribe(ctx.session(), &p.topic))
+        });
+        let hub = Arc::clone(self);
+        router.register_with_context("unsubscribe", move |ctx: &Context, p: UnsubscribeParams| {
+            Ok::<_, RpcError>(hub.unsubscribe(ctx.session(), p.subscription))
+        });
+        let hub = Arc::clone(self);
+        router.register("publish", move |p: PublishParams| {
+            Ok::<_, RpcError>(hub.publish(&p.topic, &p.data))
+        });
+        router
+            .describe(
+                "subscribe",
+                "Subscribes this session to a topic; returns the subscription id.",
+            )
+            .describe(
+                "unsubscribe",
+                "Cancels a subscription made by this session.",
+            )
+            .describe(
+                "publish",
+                "Sends data to every subscriber of a topic; returns the number of receivers.",
+            );
+    }
+}
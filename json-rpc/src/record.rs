@@ -0,0 +1,190 @@
+//! 录制与回放：把每次调用及其结果写成 JSONL，之后无需真实后端即可按录制结果应答。
+
+use crate::error::RpcError;
+use crate::message::Request;
+use crate::middleware::{Middleware, Next};
+use crate::session::Context;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, LineWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Methods the server provides itself, see [`Replay`].
+const BUILTIN_PREFIX: &str = "rpc.";
+
+/// One line of a recording: a request and its outcome.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    /// When the request arrived, in milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    pub duration_ms: f64,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl Exchange {
+    fn outcome(&self) -> Result<Value, RpcError> {
+        match &self.error {
+            Some(error) => Err(error.clone()),
+            None => Ok(self.result.clone().unwrap_or(Value::Null)),
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// `value` with the keys of every object in sorted order.
+fn sorted(value: &Value) -> Value {
+    match value {
+        Value::Object(members) => {
+            let mut keys: Vec<&String> = members.keys().collect();
+            keys.sort();
+            // 按顺序插入：开启 `preserve_order` 时映射保留插入顺序
+            let members = keys
+                .into_iter()
+                .map(|key| (key.clone(), sorted(&members[key])))
+                .collect::<Map<String, Value>>();
+            Value::Object(members)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(sorted).collect()),
+        other => other.clone(),
+    }
+}
+
+/// `null` params and no params are the same request. Object keys are
+/// sorted first, so the key does not depend on member order.
+fn key(method: &str, params: Option<&Value>) -> String {
+    let params = params.filter(|params| !params.is_null()).map(sorted);
+    serde_json::to_string(&(method, params)).expect("JSON values always serialize")
+}
+
+/// Appends an [`Exchange`] per request (not notifications) to a JSONL file.
+pub struct Recorder {
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+impl Recorder {
+    pub fn new<W: Write + Send + 'static>(out: W) -> Self {
+        Recorder {
+            out: Mutex::new(Box::new(out)),
+        }
+    }
+
+    /// Truncates `path` and records into it, one line per exchange.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Recorder::new(LineWriter::new(File::create(path)?)))
+    }
+}
+
+impl Middleware for Recorder {
+    fn handle(&self, ctx: &Context, request: &Request, next: Next<'_>) -> Result<Value, RpcError> {
+        let timestamp_ms = now_ms();
+        let started = Instant::now();
+        let result = next.run(ctx, request);
+        if ctx.id().is_none() {
+            return result;
+        }
+        let (ok, error) = match &result {
+            Ok(value) => (Some(value.clone()), None),
+            Err(err) => (None, Some(err.clone())),
+        };
+        let exchange = Exchange {
+            timestamp_ms,
+            duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+            method: request.method.clone(),
+            params: request.params.clone(),
+            id: ctx.id().cloned(),
+            result: ok,
+            error,
+        };
+        let line = serde_json::to_string(&exchange).expect("JSON values always serialize");
+        if let Err(err) = writeln!(self.out.lock().unwrap(), "{}", line) {
+            warn!("failed to record {}: {}", request.method, err);
+        }
+        result
+    }
+}
+
+/// Answers requests from a recording instead of running their handlers.
+///
+/// A request matches an exchange with the same method and params. Repeated
+/// requests get the recorded answers in order, and the last one once they
+/// run out. Unmatched requests fail with [`RpcError::NOT_RECORDED`].
+/// Built-in `rpc.*` methods such as `rpc.methods` and `rpc.discover` are
+/// not replayed but answered by the server itself.
+pub struct Replay {
+    exchanges: Mutex<HashMap<String, VecDeque<Exchange>>>,
+}
+
+impl Replay {
+    pub fn new<I: IntoIterator<Item = Exchange>>(exchanges: I) -> Self {
+        let mut by_key: HashMap<String, VecDeque<Exchange>> = HashMap::new();
+        for exchange in exchanges {
+            let key = key(&exchange.method, exchange.params.as_ref());
+            by_key.entry(key).or_default().push_back(exchange);
+        }
+        Replay {
+            exchanges: Mutex::new(by_key),
+        }
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut exchanges = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            exchanges.push(serde_json::from_str(&line)?);
+        }
+        Ok(Replay::new(exchanges))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Replay::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    fn lookup(&self, method: &str, params: Option<&Value>) -> Option<Result<Value, RpcError>> {
+        let mut exchanges = self.exchanges.lock().unwrap();
+        let queue = exchanges.get_mut(&key(method, params))?;
+        let exchange = if queue.len() > 1 {
+            queue.pop_front()?
+        } else {
+            queue.front()?.clone()
+        };
+        Some(exchange.outcome())
+    }
+}
+
+impl Middleware for Replay {
+    fn handle(&self, ctx: &Context, request: &Request, next: Next<'_>) -> Result<Value, RpcError> {
+        // 内置方法描述的是当前的服务端，不是录制时的那个
+        if request.method.starts_with(BUILTIN_PREFIX) {
+            return next.run(ctx, request);
+        }
+        self.lookup(&request.method, request.params.as_ref())
+            .unwrap_or_else(|| {
+                Err(RpcError::not_recorded(
+                    &request.method,
+                    request.params.as_ref(),
+                ))
+            })
+    }
+}
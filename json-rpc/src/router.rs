@@ -0,0 +1,228 @@
+use crate::discover::{self, MethodSchema};
+use crate::error::RpcError;
+use crate::message::{Request, Response, VERSION};
+use crate::middleware::{Middleware, Next};
+use crate::session::{CancelToken, Context, Session};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Notification that cancels an in-flight request: `{"id": <request id>}`.
+pub const CANCEL_METHOD: &str = "$/cancelRequest";
+
+/// Built-in method returning the sorted names of all registered methods.
+pub const METHODS_METHOD: &str = "rpc.methods";
+
+/// Built-in method returning an OpenRPC document for all registered methods.
+pub const DISCOVER_METHOD: &str = "rpc.discover";
+
+/// Notification reporting progress on a request:
+/// `{"token": <request id>, "value": ...}`. See [`Context::progress`].
+pub const PROGRESS_METHOD: &str = "$/progress";
+
+/// Optional handshake; returns server info and capabilities.
+pub const INITIALIZE_METHOD: &str = "initialize";
+
+/// Asks the server to finish in-flight requests and accept no new ones on
+/// this session. Answered once the session is idle.
+pub const SHUTDOWN_METHOD: &str = "shutdown";
+
+/// Notification ending the session; a stdio server exits with status 0 if
+/// `shutdown` came first and 1 otherwise.
+pub const EXIT_METHOD: &str = "exit";
+
+type Handler = Box<dyn Fn(&Context, Value) -> Result<Value, RpcError> + Send + Sync>;
+
+/// 方法注册表：按名称注册带类型的处理函数。
+///
+/// Params are deserialized into the handler's argument type, so a struct
+/// accepts both named (`{"a": 1, "b": 2}`) and positional (`[1, 2]`) params.
+/// A deserialization failure becomes an `Invalid params` error. The JSON
+/// Schemas of both types are recorded for `rpc.discover`.
+#[derive(Default)]
+pub struct Router {
+    methods: HashMap<String, Handler>,
+    schemas: HashMap<String, MethodSchema>,
+    timeouts: HashMap<String, Duration>,
+    default_timeout: Option<Duration>,
+    layers: Vec<Arc<dyn Middleware>>,
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Router::default()
+    }
+
+    pub fn register<P, R, F>(&mut self, name: &str, handler: F) -> &mut Self
+    where
+        P: DeserializeOwned + JsonSchema,
+        R: Serialize + JsonSchema,
+        F: Fn(P) -> Result<R, RpcError> + Send + Sync + 'static,
+    {
+        self.register_with_context(name, move |_: &Context, params: P| handler(params))
+    }
+
+    /// Like [`Router::register`], but the handler also receives the request
+    /// [`Context`], e.g. to push notifications to the calling session.
+    pub fn register_with_context<P, R, F>(&mut self, name: &str, handler: F) -> &mut Self
+    where
+        P: DeserializeOwned + JsonSchema,
+        R: Serialize + JsonSchema,
+        F: Fn(&Context, P) -> Result<R, RpcError> + Send + Sync + 'static,
+    {
+        let handler = move |ctx: &Context, params: Value| {
+            let params: P = serde_json::from_value(params).map_err(RpcError::invalid_params)?;
+            let result = handler(ctx, params)?;
+            serde_json::to_value(result).map_err(RpcError::internal_error)
+        };
+        self.methods.insert(name.to_string(), Box::new(handler));
+        let description = self
+            .schemas
+            .remove(name)
+            .and_then(|schema| schema.description);
+        let mut schema = MethodSchema::of::<P, R>();
+        schema.description = description;
+        self.schemas.insert(name.to_string(), schema);
+        self
+    }
+
+    /// Attaches a human-readable description shown by `rpc.discover`.
+    pub fn describe(&mut self, name: &str, description: &str) -> &mut Self {
+        if let Some(schema) = self.schemas.get_mut(name) {
+            schema.description = Some(description.to_string());
+        }
+        self
+    }
+
+    /// The OpenRPC document served by `rpc.discover`.
+    pub fn discover(&self) -> Value {
+        let mut names = self.method_names();
+        names.retain(|name| self.schemas.contains_key(*name));
+        discover::document(names.into_iter().map(|name| (name, &self.schemas[name])))
+    }
+
+    /// The result of the built-in `initialize` method.
+    pub fn initialize(&self) -> Value {
+        json!({
+            "serverInfo": {
+                "name": env!("CARGO_PKG_NAME"),
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "capabilities": {
+                "methods": self.method_names(),
+                "encodings": ["json", "msgpack", "cbor"],
+                "cancelRequest": true,
+                "progress": true,
+            },
+        })
+    }
+
+    /// Sets the timeout for one method, overriding the default timeout.
+    pub fn timeout(&mut self, name: &str, timeout: Duration) -> &mut Self {
+        self.timeouts.insert(name.to_string(), timeout);
+        self
+    }
+
+    /// Sets the timeout for methods without their own timeout.
+    pub fn default_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.default_timeout = Some(timeout);
+        self
+    }
+
+    pub fn timeout_for(&self, name: &str) -> Option<Duration> {
+        self.timeouts.get(name).copied().or(self.default_timeout)
+    }
+
+    /// Wraps every call in `middleware`. Layers run in the order they are
+    /// added, the first one outermost.
+    pub fn layer<M: Middleware + 'static>(&mut self, middleware: M) -> &mut Self {
+        self.layers.push(Arc::new(middleware));
+        self
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.methods.contains_key(name)
+    }
+
+    /// Whether [`Router::call`] dispatches `method`, built-in methods such
+    /// as `rpc.methods` included, rather than answering `Method not found`.
+    pub fn handles(&self, method: &str) -> bool {
+        self.contains(method)
+            || matches!(method, METHODS_METHOD | DISCOVER_METHOD | INITIALIZE_METHOD)
+    }
+
+    pub fn method_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.methods.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+
+    /// Missing params are passed to the handler as `null`.
+    pub fn call(
+        &self,
+        ctx: &Context,
+        method: &str,
+        params: Option<Value>,
+    ) -> Result<Value, RpcError> {
+        match self.methods.get(method) {
+            Some(handler) => handler(ctx, params.unwrap_or(Value::Null)),
+            None if method == METHODS_METHOD => Ok(Value::from(self.method_names())),
+            None if method == DISCOVER_METHOD => Ok(self.discover()),
+            None if method == INITIALIZE_METHOD => Ok(self.initialize()),
+            None => Err(RpcError::method_not_found(method)),
+        }
+    }
+
+    /// Dispatches a request; notifications produce no response.
+    ///
+    /// A request that gets cancelled before its handler returns is answered
+    /// with a `Request cancelled` error instead of its result.
+    pub fn handle(&self, session: &Arc<Session>, request: Request) -> Option<Response> {
+        if request.method == CANCEL_METHOD {
+            if let Some(id) = request.params.as_ref().and_then(|params| params.get("id")) {
+                session.cancel(id);
+            }
+            return request.id.map(|id| Response::success(id, Value::Null));
+        }
+        let cancel = match &request.id {
+            Some(id) => session.track(id),
+            None => CancelToken::new(),
+        };
+        let result = if request.jsonrpc != VERSION {
+            Err(RpcError::invalid_request("jsonrpc must be \"2.0\""))
+        } else if cancel.is_cancelled() {
+            Err(RpcError::request_cancelled())
+        } else {
+            let ctx = Context::new(Arc::clone(session), request.id.clone(), cancel.clone());
+            Next::new(self, &self.layers).run(&ctx, &request)
+        };
+        let id = request.id?;
+        session.finish(&id);
+        let result = if cancel.is_cancelled() {
+            Err(RpcError::request_cancelled())
+        } else {
+            result
+        };
+        Some(match result {
+            Ok(value) => Response::success(id, value),
+            Err(error) => Response::failure(id, error),
+        })
+    }
+
+    /// Dispatches an already-parsed JSON value that may not be a valid request.
+    pub fn handle_value(&self, session: &Arc<Session>, value: Value) -> Option<Response> {
+        let id = value.get("id").cloned();
+        match serde_json::from_value::<Request>(value) {
+            Ok(request) => self.handle(session, request),
+            // 这个 id 没有登记过；同 id 的请求可能还在运行，不能释放它
+            Err(err) => Some(Response::failure(
+                id.unwrap_or(Value::Null),
+                RpcError::invalid_request(err),
+            )),
+        }
+    }
+}
@@ -0,0 +1,529 @@
+use crate::encoding::Encoding;
+use crate::error::RpcError;
+use crate::framing::{FrameTooLarge, Framer};
+use crate::message::{self, Response};
+use crate::pool::WorkerPool;
+use crate::router::{Router, CANCEL_METHOD, EXIT_METHOD, SHUTDOWN_METHOD};
+use crate::session::{Pending, Session};
+use crate::timer::Timer;
+use log::{debug, info, warn};
+use serde_json::Value;
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often an idle writer checks whether the server is draining.
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Where a response goes: straight to the session, or into a batch that is
+/// sent once all of its requests are answered.
+enum Sink {
+    Session(Arc<Session>),
+    Batch(Arc<Batch>),
+}
+
+struct Batch {
+    session: Arc<Session>,
+    state: Mutex<(usize, Vec<Value>)>,
+}
+
+impl Batch {
+    fn add(&self, response: Option<Value>) {
+        let mut state = self.state.lock().unwrap();
+        let (remaining, responses) = &mut *state;
+        responses.extend(response);
+        *remaining -= 1;
+        // 全是通知的批量请求不需要回复
+        if *remaining == 0 && !responses.is_empty() {
+            self.session.send(Value::Array(std::mem::take(responses)));
+        }
+    }
+}
+
+/// Answers a message that is not dispatched, e.g. one that could not be
+/// parsed (`id` is then `null`).
+fn send_error(session: &Session, id: Value, error: RpcError) {
+    if let Ok(response) = serde_json::to_value(Response::failure(id, error)) {
+        session.send(response);
+    }
+}
+
+/// Delivers exactly one outcome per request, whichever of the handler and
+/// the timeout finishes first. A reply dropped without an outcome, e.g.
+/// because the handler panicked, answers with an `Internal error`.
+struct Reply {
+    sent: AtomicBool,
+    /// The request id, `None` for notifications.
+    id: Option<Value>,
+    sink: Sink,
+    _busy: Busy,
+    _pending: Option<Pending>,
+}
+
+impl Reply {
+    fn complete(&self, response: Option<Response>) {
+        if self.sent.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let response = response.and_then(|response| serde_json::to_value(response).ok());
+        match &self.sink {
+            Sink::Session(session) => {
+                if let Some(response) = response {
+                    session.send(response);
+                }
+            }
+            Sink::Batch(batch) => batch.add(response),
+        }
+    }
+}
+
+impl Drop for Reply {
+    fn drop(&mut self) {
+        if self.sent.load(Ordering::SeqCst) {
+            return;
+        }
+        // 处理函数 panic 了：仍然要回复，批量请求也要凑齐
+        let id = self.id.take();
+        self.complete(
+            id.map(|id| Response::failure(id, RpcError::internal_error("the handler panicked"))),
+        );
+    }
+}
+
+#[derive(Default)]
+struct Counts {
+    requests: usize,
+    writers: usize,
+}
+
+/// Server-wide count of running requests and connection writers, so that a
+/// shutdown can wait until every response has been written.
+#[derive(Default)]
+struct Activity {
+    draining: AtomicBool,
+    counts: Mutex<Counts>,
+    changed: Condvar,
+}
+
+impl Activity {
+    fn enter(self: &Arc<Self>, writer: bool) -> Busy {
+        let mut counts = self.counts.lock().unwrap();
+        if writer {
+            counts.writers += 1;
+        } else {
+            counts.requests += 1;
+        }
+        Busy {
+            activity: Arc::clone(self),
+            writer,
+        }
+    }
+}
+
+/// Keeps a request or a writer counted until dropped.
+pub(crate) struct Busy {
+    activity: Arc<Activity>,
+    writer: bool,
+}
+
+impl Drop for Busy {
+    fn drop(&mut self) {
+        let mut counts = self.activity.counts.lock().unwrap();
+        if self.writer {
+            counts.writers -= 1;
+        } else {
+            counts.requests -= 1;
+        }
+        self.activity.changed.notify_all();
+    }
+}
+
+/// How [`Server::serve`] ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionEnd {
+    /// The input closed, or the server drained, without an `exit`.
+    Closed,
+    /// The peer sent `exit`; `shutdown` tells whether `shutdown` came first.
+    Exit { shutdown: bool },
+}
+
+/// Workers kept apart from the pool for requests that arrive while a session
+/// waits for the peer, see [`Session::call`].
+const RESERVE_WORKERS: usize = 2;
+
+/// Bounds on what a peer may send, see [`Server::with_limits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Longer messages are skipped unread and answered with an
+    /// `Invalid Request` error.
+    pub max_message_size: usize,
+    /// Messages whose arrays and objects nest deeper are answered with an
+    /// `Invalid Request` error without being dispatched.
+    pub max_depth: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_message_size: 16 * 1024 * 1024,
+            max_depth: 64,
+        }
+    }
+}
+
+/// 路由表加上工作线程池，所有传输层共享同一个 `Server`。
+///
+/// Requests run concurrently on the pool and each response is sent to its
+/// session as soon as it is ready, so responses may arrive out of order.
+/// A request that outlives its timeout (see [`Router::timeout`]) is answered
+/// with a `Request timed out` error and its cancel token is set; the handler
+/// keeps its worker until it returns.
+///
+/// The lifecycle methods are handled here rather than by the router:
+/// `shutdown` is answered once the session's in-flight requests are done and
+/// later requests are rejected, `exit` ends the session.
+#[derive(Clone)]
+pub struct Server {
+    router: Arc<Router>,
+    pool: Arc<WorkerPool>,
+    /// Runs the requests a peer sends while a session waits for its answer
+    /// to [`Session::call`], so that they cannot queue behind the waiting
+    /// handler.
+    reserve: Arc<WorkerPool>,
+    timer: Arc<Timer>,
+    activity: Arc<Activity>,
+    limits: Limits,
+}
+
+impl Server {
+    pub fn new(router: Router) -> Self {
+        Server::with_pool(router, WorkerPool::default())
+    }
+
+    /// Limits how many requests run at the same time across all sessions.
+    pub fn with_workers(router: Router, workers: usize) -> Self {
+        Server::with_pool(router, WorkerPool::new(workers))
+    }
+
+    fn with_pool(router: Router, pool: WorkerPool) -> Self {
+        Server {
+            router: Arc::new(router),
+            pool: Arc::new(pool),
+            reserve: Arc::new(WorkerPool::new(RESERVE_WORKERS)),
+            timer: Arc::new(Timer::new()),
+            activity: Arc::default(),
+            limits: Limits::default(),
+        }
+    }
+
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    pub fn router(&self) -> &Arc<Router> {
+        &self.router
+    }
+
+    pub fn workers(&self) -> usize {
+        self.pool.size()
+    }
+
+    /// Stops accepting requests on every session and waits up to `grace` for
+    /// the running ones to finish and their responses to be written.
+    /// Returns `false` if the grace period ran out first.
+    pub fn shutdown(&self, grace: Duration) -> bool {
+        info!("draining requests");
+        self.activity.draining.store(true, Ordering::SeqCst);
+        let deadline = Instant::now() + grace;
+        let mut counts = self.activity.counts.lock().unwrap();
+        while counts.requests > 0 || counts.writers > 0 {
+            let now = Instant::now();
+            if now >= deadline {
+                warn!(
+                    "shutdown grace period over with {} requests in flight",
+                    counts.requests
+                );
+                return false;
+            }
+            counts = self
+                .activity
+                .changed
+                .wait_timeout(counts, deadline - now)
+                .unwrap()
+                .0;
+        }
+        true
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.activity.draining.load(Ordering::SeqCst)
+    }
+
+    /// Draining and no request left running: a writer that finds its queue
+    /// empty now can close its connection.
+    pub(crate) fn is_drained(&self) -> bool {
+        self.is_draining() && self.activity.counts.lock().unwrap().requests == 0
+    }
+
+    /// Counts a connection writer until the returned guard is dropped;
+    /// [`Server::shutdown`] waits for it.
+    pub(crate) fn writer_busy(&self) -> Busy {
+        self.activity.enter(true)
+    }
+
+    /// Queues `message`, a single request or a batch, on the worker pool.
+    /// Its response, if any, is sent through `session`. Responses from the
+    /// peer go to the [`Session::call`] waiting for them.
+    pub fn submit(&self, session: &Arc<Session>, message: Value) {
+        if message::nesting_depth(&message) > self.limits.max_depth {
+            let detail = format!("nesting deeper than {} levels", self.limits.max_depth);
+            warn!("rejecting message: {}", detail);
+            // 只有简单的 id 才值得回显
+            let id = message
+                .get("id")
+                .filter(|id| id.is_string() || id.is_number())
+                .cloned()
+                .unwrap_or(Value::Null);
+            send_error(session, id, RpcError::invalid_request(detail));
+            return;
+        }
+        match message {
+            Value::Array(batch) if batch.is_empty() => {
+                send_error(
+                    session,
+                    Value::Null,
+                    RpcError::invalid_request("empty batch"),
+                );
+            }
+            Value::Array(batch) => {
+                let sink = Arc::new(Batch {
+                    session: Arc::clone(session),
+                    state: Mutex::new((batch.len(), Vec::new())),
+                });
+                for request in batch {
+                    self.submit_one(session, request, Sink::Batch(Arc::clone(&sink)));
+                }
+            }
+            request => self.submit_one(session, request, Sink::Session(Arc::clone(session))),
+        }
+    }
+
+    fn submit_one(&self, session: &Arc<Session>, request: Value, sink: Sink) {
+        let method = request
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let reply = Arc::new(Reply {
+            sent: AtomicBool::new(false),
+            id: request.get("id").cloned(),
+            sink,
+            _busy: self.activity.enter(false),
+            // shutdown 自己等待会话空闲，不能计入
+            _pending: (method != SHUTDOWN_METHOD).then(|| session.pending()),
+        });
+        // 对端对 Session::call 的回复
+        if message::is_response(&request) {
+            match serde_json::from_value(request) {
+                Ok(response) => session.calls().resolve(response),
+                Err(err) => warn!("ignoring malformed response: {}", err),
+            }
+            reply.complete(None);
+            return;
+        }
+        match method {
+            EXIT_METHOD => {
+                session.exit();
+                reply.complete(None);
+                return;
+            }
+            CANCEL_METHOD => {}
+            _ if session.is_shutting_down() || self.is_draining() => {
+                let id = request.get("id").cloned();
+                reply.complete(id.map(|id| Response::failure(id, RpcError::shutting_down())));
+                return;
+            }
+            SHUTDOWN_METHOD => {
+                session.begin_shutdown();
+                // 在单独的线程里等会话空闲，不占用工作线程
+                let (session, id) = (Arc::clone(session), request.get("id").cloned());
+                thread::spawn(move || {
+                    session.wait_idle();
+                    reply.complete(id.map(|id| Response::success(id, Value::Null)));
+                });
+                return;
+            }
+            _ => {}
+        }
+        // 先登记请求，这样排队中的请求也能被取消。
+        // 解析不了的消息不登记：由 Router::handle 登记的 id 才会被释放
+        if let Some(id) = request
+            .get("id")
+            .filter(|_| method != CANCEL_METHOD && message::is_well_formed(&request))
+        {
+            session.track(id);
+            if let Some(timeout) = self.router.timeout_for(method) {
+                // 只持有弱引用：请求完成后不再拖住会话
+                let (reply, session, id) =
+                    (Arc::downgrade(&reply), Arc::downgrade(session), id.clone());
+                self.timer.schedule(timeout, move || {
+                    if let Some(reply) = reply.upgrade() {
+                        reply.complete(Some(Response::failure(
+                            id.clone(),
+                            RpcError::timeout(timeout),
+                        )));
+                    }
+                    if let Some(session) = session.upgrade() {
+                        session.cancel(&id);
+                    }
+                });
+            }
+        }
+        // 取消通知不能排在它要取消的请求后面，直接在当前线程处理
+        if method == CANCEL_METHOD {
+            reply.complete(self.router.handle_value(session, request));
+            return;
+        }
+        // 会话在等对端回复时，对端发来的请求可能正是那个回复所依赖的；
+        // 不能让它排在被占住的工作线程后面，否则会死锁
+        // 这类请求交给固定大小的备用线程池，而不是每个请求开一个线程
+        let pool = if session.calls().len() > 0 {
+            &self.reserve
+        } else {
+            &self.pool
+        };
+        let router = Arc::clone(&self.router);
+        let session = Arc::clone(session);
+        pool.execute(move || {
+            reply.complete(router.handle_value(&session, request));
+        });
+    }
+
+    /// Serves one session until the input ends or the peer sends `exit`,
+    /// then waits for in-flight requests to finish before returning. While
+    /// the server drains, it returns as soon as its responses are written.
+    ///
+    /// Responses and server-initiated notifications share one writer thread,
+    /// so only framed protocol messages are written to `writer`; diagnostics
+    /// go through the `log` facade.
+    ///
+    /// Frames labelled with a `Content-Type` are decoded accordingly, and from
+    /// then on messages are sent back in that [`Encoding`].
+    pub fn serve<R, W>(
+        &self,
+        framer: &dyn Framer,
+        mut reader: R,
+        mut writer: W,
+    ) -> io::Result<SessionEnd>
+    where
+        R: BufRead,
+        W: Write + Send,
+    {
+        let (outbound, messages) = mpsc::channel::<Value>();
+        let session = Session::new(outbound);
+        debug!("session {} opened", session.id());
+        // 未协商时按原样发送不带 Content-Type 的 JSON
+        let negotiated = Mutex::new(None::<Encoding>);
+
+        thread::scope(|scope| {
+            let negotiated = &negotiated;
+            let writer = scope.spawn(move || -> io::Result<()> {
+                let _busy = self.writer_busy();
+                let mut write = |message: Value| -> io::Result<()> {
+                    debug!("send: {}", message);
+                    let encoding = *negotiated.lock().unwrap();
+                    match encoding {
+                        Some(encoding) => framer.write_typed_frame(
+                            &mut writer,
+                            &encoding.encode(&message)?,
+                            encoding.content_type(),
+                        ),
+                        None => framer.write_frame(&mut writer, &serde_json::to_vec(&message)?),
+                    }
+                };
+                loop {
+                    match messages.recv_timeout(POLL_INTERVAL) {
+                        Ok(message) => write(message)?,
+                        Err(RecvTimeoutError::Timeout) if self.is_drained() => {
+                            // 响应总是先入队、请求计数后减少，所以此时队列里就是全部剩余消息
+                            for message in messages.try_iter() {
+                                write(message)?;
+                            }
+                            return Ok(());
+                        }
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => return Ok(()),
+                    }
+                }
+            });
+
+            let read = (|| {
+                let max_size = self.limits.max_message_size;
+                loop {
+                    let (frame, content_type) =
+                        match framer.read_limited_frame(&mut reader, max_size) {
+                            Ok(Some(frame)) => frame,
+                            Ok(None) => return Ok(SessionEnd::Closed),
+                            // 超长的消息已被跳过，回复错误后继续读下一条
+                            Err(err) => match FrameTooLarge::find(&err) {
+                                Some(too_large) => {
+                                    warn!("rejecting message: {}", too_large);
+                                    send_error(
+                                        &session,
+                                        Value::Null,
+                                        RpcError::invalid_request(too_large),
+                                    );
+                                    continue;
+                                }
+                                None => return Err(err),
+                            },
+                        };
+                    let encoding = match content_type.as_deref().map(Encoding::from_content_type) {
+                        Some(Some(encoding)) => {
+                            *negotiated.lock().unwrap() = Some(encoding);
+                            encoding
+                        }
+                        Some(None) => {
+                            let detail = format!("unsupported content type {:?}", content_type);
+                            warn!("rejecting message: {}", detail);
+                            send_error(&session, Value::Null, RpcError::parse_error(detail));
+                            continue;
+                        }
+                        None => Encoding::Json,
+                    };
+                    match encoding.decode(&frame) {
+                        Ok(request) => {
+                            debug!("request: {}", request);
+                            self.submit(&session, request);
+                        }
+                        Err(err) => {
+                            warn!("unparsable message: {}", err);
+                            send_error(&session, Value::Null, RpcError::parse_error(err));
+                        }
+                    }
+                    if session.has_exited() {
+                        return Ok(SessionEnd::Exit {
+                            shutdown: session.is_shutting_down(),
+                        });
+                    }
+                }
+            })();
+            debug!("session {} input closed", session.id());
+            // 等待客户端回复的调用不会再有结果
+            session.calls().close();
+            // 释放发送端；等所有进行中的请求都完成后，写线程才会退出
+            drop(session);
+            let written = writer
+                .join()
+                .unwrap_or_else(|_| Err(io::Error::other("writer thread panicked")));
+            read.and_then(|end| written.map(|()| end))
+        })
+    }
+}
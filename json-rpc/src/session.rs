@@ -0,0 +1,298 @@
+use crate::calls::{structured, Calls};
+use crate::error::RpcError;
+use crate::message::VERSION;
+use crate::router::PROGRESS_METHOD;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+static NEXT_SESSION: AtomicU64 = AtomicU64::new(1);
+
+/// 一个客户端连接。服务端可以通过它主动推送消息。
+///
+/// Messages sent through a session are written by the connection's writer,
+/// interleaved with responses. A detached session silently drops anything
+/// pushed to it.
+///
+/// The session can also call methods on the peer with [`Session::call`];
+/// the peer's responses are matched by id like a client's.
+pub struct Session {
+    id: u64,
+    outbound: Option<Sender<Value>>,
+    /// Whether the peer can receive requests, see [`Session::one_way`].
+    callable: bool,
+    calls: Calls,
+    in_flight: Mutex<HashMap<String, CancelToken>>,
+    pending: Mutex<usize>,
+    idle: Condvar,
+    shutting_down: AtomicBool,
+    exited: AtomicBool,
+}
+
+/// Shared flag a handler can poll to notice `$/cancelRequest`.
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+fn request_key(id: &Value) -> String {
+    id.to_string()
+}
+
+impl Session {
+    pub fn new(outbound: Sender<Value>) -> Arc<Self> {
+        Session::create(Session::next_id(), Some(outbound), true)
+    }
+
+    pub fn detached() -> Arc<Self> {
+        Session::create(Session::next_id(), None, true)
+    }
+
+    /// A session whose peer can only answer requests, e.g. one HTTP request:
+    /// responses and notifications are queued as usual, but
+    /// [`Session::call`] fails straight away with a transport error.
+    /// Sessions for requests on the same connection share its `id`.
+    pub(crate) fn one_way(id: u64, outbound: Sender<Value>) -> Arc<Self> {
+        Session::create(id, Some(outbound), false)
+    }
+
+    /// A fresh id, for a connection that creates its sessions with
+    /// [`Session::one_way`].
+    pub(crate) fn next_id() -> u64 {
+        NEXT_SESSION.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn create(id: u64, outbound: Option<Sender<Value>>, callable: bool) -> Arc<Self> {
+        Arc::new(Session {
+            id,
+            outbound,
+            callable,
+            calls: Calls::default(),
+            in_flight: Mutex::default(),
+            pending: Mutex::new(0),
+            idle: Condvar::new(),
+            shutting_down: AtomicBool::new(false),
+            exited: AtomicBool::new(false),
+        })
+    }
+
+    /// Identifies the connection, e.g. for logs and rate limits.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Queues a raw message; returns `false` once the connection is gone.
+    pub fn send(&self, message: Value) -> bool {
+        match &self.outbound {
+            Some(outbound) => outbound.send(message).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Returns the cancel token for request `id`, creating it on first use.
+    pub fn track(&self, id: &Value) -> CancelToken {
+        self.in_flight
+            .lock()
+            .unwrap()
+            .entry(request_key(id))
+            .or_default()
+            .clone()
+    }
+
+    pub fn finish(&self, id: &Value) {
+        self.in_flight.lock().unwrap().remove(&request_key(id));
+    }
+
+    /// Counts a submitted message until its response, if any, is queued.
+    pub(crate) fn pending(self: &Arc<Self>) -> Pending {
+        *self.pending.lock().unwrap() += 1;
+        Pending(Arc::clone(self))
+    }
+
+    /// Blocks until every submitted request and notification has been
+    /// handled and its response queued.
+    pub fn wait_idle(&self) {
+        let pending = self.pending.lock().unwrap();
+        let _idle = self
+            .idle
+            .wait_while(pending, |pending| *pending > 0)
+            .unwrap();
+    }
+
+    /// After `shutdown` the session accepts no new requests.
+    pub fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    /// Handles `exit`: cancels whatever is still running and marks the
+    /// session as finished.
+    pub fn exit(&self) {
+        self.exited.store(true, Ordering::SeqCst);
+        for token in self.in_flight.lock().unwrap().values() {
+            token.cancel();
+        }
+    }
+
+    pub fn has_exited(&self) -> bool {
+        self.exited.load(Ordering::SeqCst)
+    }
+
+    /// Cancels the in-flight request `id`; unknown ids are ignored.
+    pub fn cancel(&self, id: &Value) -> bool {
+        match self.in_flight.lock().unwrap().get(&request_key(id)) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sends a notification (a request without `id`) to the peer.
+    pub fn notify(&self, method: &str, params: Value) -> bool {
+        self.send(json!({"jsonrpc": VERSION, "method": method, "params": params}))
+    }
+
+    /// Calls `method` on the peer and waits for its response, e.g. to ask
+    /// the user for confirmation. Fails with a transport error on a detached
+    /// or one-way session, or once the connection closes.
+    pub fn call<P, R>(&self, method: &str, params: P) -> Result<R, RpcError>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        self.request(method, params, None)
+    }
+
+    /// Like [`Session::call`], but gives up after `timeout` and tells the
+    /// peer to cancel the request.
+    pub fn call_with_timeout<P, R>(
+        &self,
+        method: &str,
+        params: P,
+        timeout: Duration,
+    ) -> Result<R, RpcError>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        self.request(method, params, Some(timeout))
+    }
+
+    fn request<P, R>(
+        &self,
+        method: &str,
+        params: P,
+        timeout: Option<Duration>,
+    ) -> Result<R, RpcError>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        // HTTP 之类的传输没有办法把请求发给对端，直接失败而不是一直等下去
+        if !self.callable {
+            return Err(RpcError::transport(
+                "the transport cannot carry calls to the peer",
+            ));
+        }
+        let params = serde_json::to_value(params).map_err(RpcError::invalid_params)?;
+        let result = self
+            .calls
+            .call(method, structured(params), timeout, |request| {
+                let message = serde_json::to_value(request).map_err(RpcError::internal_error)?;
+                if self.send(message) {
+                    Ok(())
+                } else {
+                    Err(RpcError::transport("connection closed"))
+                }
+            })?;
+        serde_json::from_value(result).map_err(RpcError::internal_error)
+    }
+
+    /// Calls made through this session that still wait for the peer.
+    pub(crate) fn calls(&self) -> &Calls {
+        &self.calls
+    }
+}
+
+/// See [`Session::pending`].
+pub(crate) struct Pending(Arc<Session>);
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        let mut pending = self.0.pending.lock().unwrap();
+        *pending -= 1;
+        if *pending == 0 {
+            self.0.idle.notify_all();
+        }
+    }
+}
+
+/// Per-request state handed to handlers registered with a context.
+pub struct Context {
+    session: Arc<Session>,
+    id: Option<Value>,
+    cancel: CancelToken,
+}
+
+impl Context {
+    pub fn new(session: Arc<Session>, id: Option<Value>, cancel: CancelToken) -> Self {
+        Context {
+            session,
+            id,
+            cancel,
+        }
+    }
+
+    pub fn session(&self) -> &Arc<Session> {
+        &self.session
+    }
+
+    /// The request id, `None` for notifications.
+    pub fn id(&self) -> Option<&Value> {
+        self.id.as_ref()
+    }
+
+    /// Long-running handlers should check this periodically and bail out.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    pub fn cancel_token(&self) -> &CancelToken {
+        &self.cancel
+    }
+
+    /// Reports progress to the caller as a `$/progress` notification whose
+    /// `token` is this request's id. Progress always arrives before the
+    /// response. Returns `false` for notifications, which have no id, and
+    /// once the connection is gone.
+    pub fn progress<T: Serialize>(&self, value: T) -> bool {
+        let (id, value) = match (&self.id, serde_json::to_value(value)) {
+            (Some(id), Ok(value)) => (id, value),
+            _ => return false,
+        };
+        self.session
+            .notify(PROGRESS_METHOD, json!({"token": id, "value": value}))
+    }
+}
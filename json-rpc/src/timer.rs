@@ -0,0 +1,107 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+type Task = Box<dyn FnOnce() + Send>;
+
+struct Entry {
+    deadline: Instant,
+    seq: u64,
+    task: Task,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        (self.deadline, self.seq) == (other.deadline, other.seq)
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    // BinaryHeap 是大顶堆，反过来比较让最早的截止时间排在堆顶
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.deadline, other.seq).cmp(&(self.deadline, self.seq))
+    }
+}
+
+#[derive(Default)]
+struct State {
+    entries: BinaryHeap<Entry>,
+    next_seq: u64,
+    closed: bool,
+}
+
+/// A single background thread that runs tasks once their deadline passes.
+pub struct Timer {
+    shared: Arc<(Mutex<State>, Condvar)>,
+}
+
+impl Timer {
+    pub fn new() -> Self {
+        let shared = Arc::new((Mutex::new(State::default()), Condvar::new()));
+        let worker = Arc::clone(&shared);
+        thread::Builder::new()
+            .name("rpc-timer".to_string())
+            .spawn(move || run(&worker))
+            .expect("failed to spawn timer thread");
+        Timer { shared }
+    }
+
+    /// Runs `task` on the timer thread after `delay`.
+    pub fn schedule<F: FnOnce() + Send + 'static>(&self, delay: Duration, task: F) {
+        let (state, wakeup) = &*self.shared;
+        let mut state = state.lock().unwrap();
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        state.entries.push(Entry {
+            deadline: Instant::now() + delay,
+            seq,
+            task: Box::new(task),
+        });
+        wakeup.notify_one();
+    }
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Timer::new()
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        let (state, wakeup) = &*self.shared;
+        state.lock().unwrap().closed = true;
+        wakeup.notify_one();
+    }
+}
+
+fn run(shared: &(Mutex<State>, Condvar)) {
+    let (state, wakeup) = shared;
+    let mut guard = state.lock().unwrap();
+    loop {
+        if guard.closed {
+            return;
+        }
+        let now = Instant::now();
+        match guard.entries.peek().map(|entry| entry.deadline) {
+            Some(deadline) if deadline <= now => {
+                let entry = guard.entries.pop().expect("peeked entry");
+                drop(guard);
+                (entry.task)();
+                guard = state.lock().unwrap();
+            }
+            Some(deadline) => guard = wakeup.wait_timeout(guard, deadline - now).unwrap().0,
+            None => guard = wakeup.wait(guard).unwrap(),
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! TCP 上的 TLS：证书和私钥从 PEM 文件加载，可选要求客户端出示证书（双向 TLS）。
+//!
+//! 会话的读线程和写线程各自持有连接的一半：TLS 状态放在互斥锁里，
+//! 阻塞读 socket 时不持锁，所以等待请求的同时仍然可以写出响应。
+
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{
+    ClientConfig, ClientConnection, Connection, RootCertStore, ServerConfig, ServerConnection,
+};
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub use rustls;
+
+/// How long a peer gets to finish the handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn invalid_data(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+fn open(path: &Path) -> io::Result<BufReader<File>> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))
+}
+
+/// Loads every certificate in a PEM file.
+pub fn load_certs(path: &Path) -> io::Result<Vec<CertificateDer<'static>>> {
+    let certs = rustls_pemfile::certs(&mut open(path)?).collect::<io::Result<Vec<_>>>()?;
+    if certs.is_empty() {
+        return Err(invalid_data(format!(
+            "{}: no certificates found",
+            path.display()
+        )));
+    }
+    Ok(certs)
+}
+
+/// Loads the first private key (PKCS#8, PKCS#1 or SEC1) in a PEM file.
+pub fn load_key(path: &Path) -> io::Result<PrivateKeyDer<'static>> {
+    rustls_pemfile::private_key(&mut open(path)?)?
+        .ok_or_else(|| invalid_data(format!("{}: no private key found", path.display())))
+}
+
+fn roots(path: &Path) -> io::Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(path)? {
+        roots.add(cert).map_err(invalid_data)?;
+    }
+    Ok(roots)
+}
+
+/// Server configuration presenting `cert`/`key`. With `client_ca`, clients
+/// must present a certificate signed by one of its certificates.
+pub fn server_config(
+    cert: &Path,
+    key: &Path,
+    client_ca: Option<&Path>,
+) -> io::Result<Arc<ServerConfig>> {
+    let builder = ServerConfig::builder();
+    let builder = match client_ca {
+        Some(path) => {
+            let verifier = WebPkiClientVerifier::builder(Arc::new(roots(path)?))
+                .build()
+                .map_err(invalid_data)?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let config = builder
+        .with_single_cert(load_certs(cert)?, load_key(key)?)
+        .map_err(invalid_data)?;
+    Ok(Arc::new(config))
+}
+
+/// Client configuration trusting the certificates in `ca`, optionally
+/// authenticating with a client certificate and key.
+pub fn client_config(ca: &Path, identity: Option<(&Path, &Path)>) -> io::Result<Arc<ClientConfig>> {
+    let builder = ClientConfig::builder().with_root_certificates(roots(ca)?);
+    let config = match identity {
+        Some((cert, key)) => builder
+            .with_client_auth_cert(load_certs(cert)?, load_key(key)?)
+            .map_err(invalid_data)?,
+        None => builder.with_no_client_auth(),
+    };
+    Ok(Arc::new(config))
+}
+
+/// Runs the server side of the handshake on `stream`.
+pub fn accept(stream: TcpStream, config: Arc<ServerConfig>) -> io::Result<(TlsReader, TlsWriter)> {
+    let connection = ServerConnection::new(config).map_err(invalid_data)?;
+    handshake(stream, connection.into())
+}
+
+/// Runs the client side of the handshake on `stream`, checking that the
+/// server's certificate is valid for `server_name`.
+pub fn connect(
+    stream: TcpStream,
+    server_name: &str,
+    config: Arc<ClientConfig>,
+) -> io::Result<(TlsReader, TlsWriter)> {
+    let name = ServerName::try_from(server_name.to_string()).map_err(invalid_data)?;
+    let connection = ClientConnection::new(config, name).map_err(invalid_data)?;
+    handshake(stream, connection.into())
+}
+
+fn handshake(
+    mut stream: TcpStream,
+    mut connection: Connection,
+) -> io::Result<(TlsReader, TlsWriter)> {
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    while connection.is_handshaking() {
+        connection.complete_io(&mut stream)?;
+    }
+    stream.set_read_timeout(None)?;
+    let shared = Arc::new(Shared {
+        connection: Mutex::new(connection),
+        socket: stream,
+    });
+    let reader = TlsReader {
+        shared: Arc::clone(&shared),
+        incoming: Vec::new(),
+        consumed: 0,
+    };
+    Ok((reader, TlsWriter { shared }))
+}
+
+struct Shared {
+    connection: Mutex<Connection>,
+    socket: TcpStream,
+}
+
+impl Shared {
+    /// Sends whatever TLS records the connection has queued.
+    fn flush_tls(&self, connection: &mut Connection) -> io::Result<()> {
+        while connection.wants_write() {
+            connection.write_tls(&mut &self.socket)?;
+        }
+        Ok(())
+    }
+}
+
+/// The reading half of a TLS connection.
+pub struct TlsReader {
+    shared: Arc<Shared>,
+    /// Ciphertext read from the socket but not yet handed to rustls.
+    incoming: Vec<u8>,
+    consumed: usize,
+}
+
+impl TlsReader {
+    /// The underlying socket, e.g. to shut it down from another thread.
+    pub fn socket(&self) -> io::Result<TcpStream> {
+        self.shared.socket.try_clone()
+    }
+}
+
+impl Read for TlsReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            {
+                let mut connection = self.shared.connection.lock().unwrap();
+                match connection.reader().read(buf) {
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                    result => return result,
+                }
+                if self.consumed < self.incoming.len() {
+                    let mut rest = &self.incoming[self.consumed..];
+                    let before = rest.len();
+                    connection.read_tls(&mut rest)?;
+                    self.consumed += before - rest.len();
+                    let processed = connection.process_new_packets();
+                    // 出错时也要把告警发给对端
+                    self.shared.flush_tls(&mut connection)?;
+                    processed.map_err(invalid_data)?;
+                    continue;
+                }
+            }
+            // 缓冲区已经交给 rustls，不持锁地等待新数据
+            self.incoming.resize(16 * 1024, 0);
+            let read = (&self.shared.socket).read(&mut self.incoming)?;
+            self.incoming.truncate(read);
+            self.consumed = 0;
+            if read == 0 {
+                return Ok(0);
+            }
+        }
+    }
+}
+
+/// The writing half of a TLS connection.
+pub struct TlsWriter {
+    shared: Arc<Shared>,
+}
+
+impl Write for TlsWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut connection = self.shared.connection.lock().unwrap();
+        let written = connection.writer().write(buf)?;
+        self.shared.flush_tls(&mut connection)?;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut connection = self.shared.connection.lock().unwrap();
+        connection.writer().flush()?;
+        self.shared.flush_tls(&mut connection)?;
+        (&self.shared.socket).flush()
+    }
+}
+
+impl Drop for TlsWriter {
+    fn drop(&mut self) {
+        // 告诉对端不会再有数据（close_notify），读端仍可继续读取
+        if let Ok(mut connection) = self.shared.connection.lock() {
+            connection.send_close_notify();
+            let _ = self.shared.flush_tls(&mut connection);
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! 把工作区里的其他程序（计算器、词频统计、图书目录、待办事项）暴露为 RPC 方法。
+
+use crate::error::RpcError;
+use crate::router::Router;
+use library::{AdvancedLibrary, Book, BookGenre, Library, LibraryError};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use todo_manager::{Task, TaskManager};
+use word_counter::count_words;
+
+/// No book or task with the given title or id.
+pub const NOT_FOUND: i64 = 1;
+/// A book with the same title is already in the catalog.
+pub const ALREADY_EXISTS: i64 = 2;
+
+#[derive(Deserialize, JsonSchema)]
+struct EvalParams {
+    /// Arithmetic expression using `+ - * /` and parentheses
+    expression: String,
+}
+
+fn default_top() -> usize {
+    10
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct TopParams {
+    text: String,
+    #[serde(default = "default_top")]
+    n: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct WordCount {
+    word: String,
+    count: u32,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+enum Genre {
+    Fiction,
+    NonFiction,
+    TextBook,
+}
+
+impl From<Genre> for BookGenre {
+    fn from(genre: Genre) -> Self {
+        match genre {
+            Genre::Fiction => BookGenre::Fiction,
+            Genre::NonFiction => BookGenre::NonFiction,
+            Genre::TextBook => BookGenre::TextBook,
+        }
+    }
+}
+
+impl From<&BookGenre> for Genre {
+    fn from(genre: &BookGenre) -> Self {
+        match genre {
+            BookGenre::Fiction => Genre::Fiction,
+            BookGenre::NonFiction => Genre::NonFiction,
+            BookGenre::TextBook => Genre::TextBook,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct BookInfo {
+    title: String,
+    author: String,
+    year: u32,
+    genre: Genre,
+}
+
+impl From<&Book> for BookInfo {
+    fn from(book: &Book) -> Self {
+        BookInfo {
+            title: book.title.clone(),
+            author: book.author.clone(),
+            year: book.year,
+            genre: Genre::from(&book.genre),
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct TitleParams {
+    title: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct GenreParams {
+    genre: Genre,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct TaskInfo {
+    id: usize,
+    description: String,
+    completed: bool,
+    /// RFC 3339 timestamp
+    created_at: String,
+}
+
+impl From<&Task> for TaskInfo {
+    fn from(task: &Task) -> Self {
+        TaskInfo {
+            id: task.id,
+            description: task.description.clone(),
+            completed: task.completed,
+            created_at: task.created_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct DescriptionParams {
+    description: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct IdParams {
+    id: usize,
+}
+
+fn library_error(err: LibraryError) -> RpcError {
+    let code = match err {
+        LibraryError::BookNotFound => NOT_FOUND,
+        LibraryError::DuplicateBook => ALREADY_EXISTS,
+    };
+    RpcError::new(code, err.to_string())
+}
+
+fn task_not_found(id: usize) -> RpcError {
+    RpcError::new(NOT_FOUND, format!("Task {} not found", id))
+}
+
+/// State behind the `library.*` and `todo.*` methods.
+///
+/// The catalog lives in memory; tasks are written back to `todo_file`, if
+/// one is given, after every change.
+pub struct Tools {
+    library: Mutex<AdvancedLibrary<Book>>,
+    todo: Mutex<TaskManager>,
+    todo_file: Option<PathBuf>,
+}
+
+impl Tools {
+    pub fn new(todo_file: Option<PathBuf>) -> io::Result<Arc<Self>> {
+        let todo = match &todo_file {
+            Some(path) => TaskManager::load(path)?,
+            None => TaskManager::new(),
+        };
+        Ok(Arc::new(Tools {
+            library: Mutex::new(AdvancedLibrary::new()),
+            todo: Mutex::new(todo),
+            todo_file,
+        }))
+    }
+
+    fn save(&self, todo: &TaskManager) -> Result<(), RpcError> {
+        match &self.todo_file {
+            Some(path) => todo.save(path).map_err(RpcError::internal_error),
+            None => Ok(()),
+        }
+    }
+
+    /// Registers `calc.eval`, `words.top`, `library.*` and `todo.*` on
+    /// `router`.
+    pub fn register(self: &Arc<Self>, router: &mut Router) {
+        router
+            .register("calc.eval", |p: EvalParams| {
+                let value = caculator::eval(&p.expression).map_err(RpcError::invalid_params)?;
+                // 除以零得到的无穷大和 NaN 在 JSON 里只能写成 null
+                if !value.is_finite() {
+                    return Err(RpcError::invalid_params(format!(
+                        "{} has no finite value",
+                        p.expression
+                    )));
+                }
+                Ok(value)
+            })
+            .register("words.top", |p: TopParams| {
+                let counts = count_words(&p.text);
+                let top = counts.top(p.n).into_iter().map(|(word, count)| WordCount {
+                    word: word.to_string(),
+                    count,
+                });
+                Ok::<_, RpcError>(top.collect::<Vec<_>>())
+            })
+            .describe("calc.eval", "Evaluates an arithmetic expression.")
+            .describe(
+                "words.top",
+                "Counts words in a text; returns the n most frequent (default 10).",
+            );
+        self.register_library(router);
+        self.register_todo(router);
+    }
+
+    fn register_library(self: &Arc<Self>, router: &mut Router) {
+        let tools = Arc::clone(self);
+        router.register("library.add", move |p: BookInfo| {
+            let book = Book::new(&p.title, &p.author, p.year, p.genre.into());
+            tools
+                .library
+                .lock()
+                .unwrap()
+                .add_book(book)
+                .map_err(library_error)?;
+            Ok::<_, RpcError>(p)
+        });
+        let tools = Arc::clone(self);
+        router.register("library.remove", move |p: TitleParams| {
+            let book = tools.library.lock().unwrap().remove_book(&p.title);
+            book.map(|book| BookInfo::from(&book))
+                .map_err(library_error)
+        });
+        let tools = Arc::clone(self);
+        router.register("library.get", move |p: TitleParams| {
+            let library = tools.library.lock().unwrap();
+            Ok::<_, RpcError>(library.get_book(&p.title).map(BookInfo::from))
+        });
+        let tools = Arc::clone(self);
+        router.register("library.by_genre", move |p: GenreParams| {
+            let library = tools.library.lock().unwrap();
+            let mut books: Vec<BookInfo> = library
+                .books_by_genre(&p.genre.into())
+                .into_iter()
+                .map(BookInfo::from)
+                .collect();
+            books.sort_by(|a, b| a.title.cmp(&b.title));
+            Ok::<_, RpcError>(books)
+        });
+        router
+            .describe("library.add", "Adds a book to the catalog.")
+            .describe("library.remove", "Removes a book by title and returns it.")
+            .describe("library.get", "Looks up a book by title; null if absent.")
+            .describe("library.by_genre", "Lists the books of a genre, by title.");
+    }
+
+    fn register_todo(self: &Arc<Self>, router: &mut Router) {
+        let tools = Arc::clone(self);
+        router.register("todo.add", move |p: DescriptionParams| {
+            let mut todo = tools.todo.lock().unwrap();
+            let id = todo.add_task(p.description);
+            tools.save(&todo)?;
+            Ok::<_, RpcError>(todo.get(id).map(TaskInfo::from))
+        });
+        let tools = Arc::clone(self);
+        router.register("todo.complete", move |p: IdParams| {
+            let mut todo = tools.todo.lock().unwrap();
+            if !todo.complete_task(p.id) {
+                return Err(task_not_found(p.id));
+            }
+            tools.save(&todo)?;
+            Ok(todo.get(p.id).map(TaskInfo::from))
+        });
+        let tools = Arc::clone(self);
+        router.register("todo.delete", move |p: IdParams| {
+            let mut todo = tools.todo.lock().unwrap();
+            if !todo.delete_task(p.id) {
+                return Err(task_not_found(p.id));
+            }
+            tools.save(&todo)?;
+            Ok(true)
+        });
+        let tools = Arc::clone(self);
+        router.register("todo.list", move |(): ()| {
+            let todo = tools.todo.lock().unwrap();
+            Ok::<_, RpcError>(todo.tasks().iter().map(TaskInfo::from).collect::<Vec<_>>())
+        });
+        router
+            .describe("todo.add", "Adds a task and returns it.")
+            .describe("todo.complete", "Marks a task as completed and returns it.")
+            .describe("todo.delete", "Deletes a task.")
+            .describe("todo.list", "Lists all tasks.");
+    }
+}
@@ -0,0 +1,283 @@
+//! 最小的 HTTP/1.1 传输：`POST /rpc`，请求体是单个 JSON-RPC 消息或批量数组。
+//! 请求体按 `Content-Type` 解码（JSON、MessagePack 或 CBOR），响应使用相同的编码。
+//! 请求体超过 [`Limits::max_message_size`] 时回复 `413 Payload Too Large`。
+
+use crate::encoding::Encoding;
+use crate::error::RpcError;
+use crate::framing::{read_line_limited, MAX_HEADER_LINE};
+use crate::message::Response;
+use crate::server::{Limits, Server};
+use crate::session::Session;
+use log::{debug, info, warn};
+use serde_json::Value;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+
+pub const PATH: &str = "/rpc";
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    keep_alive: bool,
+}
+
+impl HttpRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+struct HttpResponse {
+    status: u16,
+    reason: &'static str,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+impl HttpResponse {
+    fn new(status: u16, reason: &'static str) -> Self {
+        HttpResponse {
+            status,
+            reason,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    fn encoded(status: u16, reason: &'static str, encoding: Encoding, body: &Value) -> Self {
+        let mut response = HttpResponse::new(status, reason);
+        response
+            .headers
+            .push(("Content-Type", encoding.content_type().to_string()));
+        response.body = encoding.encode(body).unwrap_or_default();
+        response
+    }
+
+    fn text(status: u16, reason: &'static str) -> Self {
+        let mut response = HttpResponse::new(status, reason);
+        response
+            .headers
+            .push(("Content-Type", "text/plain; charset=utf-8".to_string()));
+        response.body = format!("{} {}\n", status, reason).into_bytes();
+        response
+    }
+
+    fn write_to<W: Write>(&self, writer: &mut W, keep_alive: bool) -> io::Result<()> {
+        write!(writer, "HTTP/1.1 {} {}\r\n", self.status, self.reason)?;
+        for (name, value) in &self.headers {
+            write!(writer, "{}: {}\r\n", name, value)?;
+        }
+        write!(writer, "Content-Length: {}\r\n", self.body.len())?;
+        let connection = if keep_alive { "keep-alive" } else { "close" };
+        write!(writer, "Connection: {}\r\n\r\n", connection)?;
+        writer.write_all(&self.body)?;
+        writer.flush()
+    }
+}
+
+/// Reads one header line; `None` if it is too long or not UTF-8.
+fn read_header_line<R: BufRead>(reader: &mut R) -> io::Result<Option<String>> {
+    let mut line = Vec::new();
+    let (consumed, truncated) = read_line_limited(reader, &mut line, MAX_HEADER_LINE)?;
+    if consumed == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    if truncated {
+        return Ok(None);
+    }
+    Ok(String::from_utf8(line).ok())
+}
+
+/// Reads one request; `Ok(None)` means the peer closed the connection.
+/// A malformed request yields the error response to send before closing.
+fn read_request<R: BufRead>(
+    reader: &mut R,
+    limits: Limits,
+) -> io::Result<Option<Result<HttpRequest, HttpResponse>>> {
+    if reader.fill_buf()?.is_empty() {
+        return Ok(None);
+    }
+    let line = match read_header_line(reader)? {
+        Some(line) => line,
+        None => {
+            return Ok(Some(Err(HttpResponse::text(
+                431,
+                "Request Header Fields Too Large",
+            ))))
+        }
+    };
+    let mut parts = line.split_whitespace();
+    let (method, path, version) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(path), Some(version)) => (method, path, version),
+        _ => return Ok(Some(Err(HttpResponse::text(400, "Bad Request")))),
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+    let mut keep_alive = version == "HTTP/1.1";
+
+    let mut headers = Vec::new();
+    loop {
+        let line = match read_header_line(reader)? {
+            Some(line) => line,
+            None => {
+                return Ok(Some(Err(HttpResponse::text(
+                    431,
+                    "Request Header Fields Too Large",
+                ))))
+            }
+        };
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() {
+            break;
+        }
+        match line.split_once(':') {
+            Some((name, value)) => {
+                headers.push((name.trim().to_string(), value.trim().to_string()))
+            }
+            None => return Ok(Some(Err(HttpResponse::text(400, "Bad Request")))),
+        }
+    }
+
+    let mut request = HttpRequest {
+        method,
+        path,
+        headers,
+        body: Vec::new(),
+        keep_alive,
+    };
+    if let Some(connection) = request.header("Connection") {
+        if connection.eq_ignore_ascii_case("close") {
+            keep_alive = false;
+        } else if connection.eq_ignore_ascii_case("keep-alive") {
+            keep_alive = true;
+        }
+    }
+    request.keep_alive = keep_alive;
+
+    if request.header("Transfer-Encoding").is_some() {
+        return Ok(Some(Err(HttpResponse::text(501, "Not Implemented"))));
+    }
+    let length = match request.header("Content-Length") {
+        Some(length) => match length.parse::<usize>() {
+            Ok(length) => length,
+            Err(_) => return Ok(Some(Err(HttpResponse::text(400, "Bad Request")))),
+        },
+        None => 0,
+    };
+    if length > limits.max_message_size {
+        return Ok(Some(Err(HttpResponse::text(413, "Payload Too Large"))));
+    }
+    request.body = vec![0; length];
+    reader.read_exact(&mut request.body)?;
+    Ok(Some(Ok(request)))
+}
+
+/// Answers one request; `connection` is the id of the sessions created for
+/// requests on this connection, so rate limits and logs see one peer.
+fn respond(server: &Server, connection: u64, request: &HttpRequest) -> HttpResponse {
+    if request.path != PATH {
+        return HttpResponse::text(404, "Not Found");
+    }
+    if request.method != "POST" {
+        let mut response = HttpResponse::text(405, "Method Not Allowed");
+        response.headers.push(("Allow", "POST".to_string()));
+        return response;
+    }
+    if request.header("Content-Length").is_none() {
+        return HttpResponse::text(411, "Length Required");
+    }
+    let encoding = match request.header("Content-Type") {
+        Some(content_type) => match Encoding::from_content_type(content_type) {
+            Some(encoding) => encoding,
+            None => return HttpResponse::text(415, "Unsupported Media Type"),
+        },
+        None => Encoding::Json,
+    };
+
+    let message = match encoding.decode(&request.body) {
+        Ok(message) => message,
+        Err(err) => {
+            let response = Response::failure(Value::Null, RpcError::parse_error(err));
+            return HttpResponse::encoded(
+                400,
+                "Bad Request",
+                encoding,
+                &serde_json::to_value(response).unwrap_or_default(),
+            );
+        }
+    };
+    debug!("http request: {}", message);
+    // 每个 HTTP 请求一个临时会话，同一连接上的会话共用一个 id；
+    // 请求处理完、会话释放后通道关闭。
+    // HTTP 的客户端无法接收服务端发起的调用，这类调用立即失败
+    let (outbound, messages) = mpsc::channel();
+    server.submit(&Session::one_way(connection, outbound), message);
+    let response = messages
+        .into_iter()
+        .find(|message| message.get("method").is_none());
+    match response {
+        Some(response) => HttpResponse::encoded(200, "OK", encoding, &response),
+        None => HttpResponse::new(204, "No Content"),
+    }
+}
+
+fn handle_connection(server: &Server, stream: TcpStream) -> io::Result<()> {
+    // 状态行、头部和正文攒在一起写出，`write_to` 最后会 flush
+    let mut writer = BufWriter::new(stream.try_clone()?);
+    let mut reader = BufReader::new(stream);
+    let connection = Session::next_id();
+    while let Some(request) = read_request(&mut reader, server.limits())? {
+        match request {
+            Ok(request) => {
+                let _busy = server.writer_busy();
+                let response = respond(server, connection, &request);
+                // 排空期间回完这个请求就关闭连接
+                let keep_alive = request.keep_alive && !server.is_draining();
+                response.write_to(&mut writer, keep_alive)?;
+                if !keep_alive {
+                    break;
+                }
+            }
+            Err(response) => {
+                response.write_to(&mut writer, false)?;
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Accepts connections forever, serving each on its own thread.
+pub fn serve(listener: TcpListener, server: Server) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!("http accept failed: {}", err);
+                continue;
+            }
+        };
+        // 与 TCP 传输一样关闭 Nagle 算法，否则 keep-alive 连接上每个响应要多等一个延迟 ACK
+        if let Err(err) = stream.set_nodelay(true) {
+            warn!("http set_nodelay failed: {}", err);
+        }
+        let server = server.clone();
+        thread::spawn(move || {
+            let peer = stream
+                .peer_addr()
+                .map(|addr| addr.to_string())
+                .unwrap_or_default();
+            info!("http connection from {}", peer);
+            if let Err(err) = handle_connection(&server, stream) {
+                warn!("http connection {} failed: {}", peer, err);
+            }
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,43 @@
+//! 监听 socket 的传输层：每个连接一个线程，共享同一个 `Server`。
+
+pub mod http;
+pub mod tcp;
+#[cfg(unix)]
+pub mod unix;
+pub mod websocket;
+
+use crate::framing::Framing;
+use crate::server::{Server, SessionEnd};
+use log::{info, warn};
+use std::io::{BufReader, Read, Write};
+use std::thread;
+
+/// Runs one client session on its own thread until the peer disconnects.
+pub(crate) fn spawn_session<R, W>(
+    server: Server,
+    framing: Framing,
+    peer: String,
+    reader: R,
+    writer: W,
+) where
+    R: Read + Send + 'static,
+    W: Write + Send + 'static,
+{
+    thread::spawn(move || {
+        info!("connection from {}", peer);
+        let framer = framing.framer();
+        match server.serve(&*framer, BufReader::new(reader), writer) {
+            Ok(SessionEnd::Closed) => info!("connection from {} closed", peer),
+            Ok(SessionEnd::Exit { shutdown }) => info!(
+                "connection from {} exited ({})",
+                peer,
+                if shutdown {
+                    "after shutdown"
+                } else {
+                    "without shutdown"
+                }
+            ),
+            Err(err) => warn!("connection from {} failed: {}", peer, err),
+        }
+    });
+}
@@ -0,0 +1,69 @@
+use super::spawn_session;
+use crate::framing::Framing;
+use crate::server::Server;
+use crate::tls;
+use log::warn;
+use rustls::ServerConfig;
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+fn peer_name(stream: &TcpStream, scheme: &str) -> String {
+    stream
+        .peer_addr()
+        .map(|addr| format!("{} {}", scheme, addr))
+        .unwrap_or_else(|_| scheme.to_string())
+}
+
+/// Accepts connections forever, serving each on its own thread.
+pub fn serve(listener: TcpListener, server: Server, framing: Framing) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!("tcp accept failed: {}", err);
+                continue;
+            }
+        };
+        let peer = peer_name(&stream, "tcp");
+        // 请求/响应都是小包，关闭 Nagle 算法降低延迟
+        if let Err(err) = stream.set_nodelay(true) {
+            warn!("tcp set_nodelay failed: {}", err);
+        }
+        let writer = stream.try_clone()?;
+        spawn_session(server.clone(), framing, peer, stream, writer);
+    }
+    Ok(())
+}
+
+/// Like [`serve`], but every connection starts with a TLS handshake.
+/// Connections whose handshake fails are dropped.
+pub fn serve_tls(
+    listener: TcpListener,
+    server: Server,
+    framing: Framing,
+    config: Arc<ServerConfig>,
+) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!("tls accept failed: {}", err);
+                continue;
+            }
+        };
+        let peer = peer_name(&stream, "tls");
+        if let Err(err) = stream.set_nodelay(true) {
+            warn!("tcp set_nodelay failed: {}", err);
+        }
+        let server = server.clone();
+        let config = Arc::clone(&config);
+        // 握手可能很慢，不能阻塞 accept 循环
+        thread::spawn(move || match tls::accept(stream, config) {
+            Ok((reader, writer)) => spawn_session(server, framing, peer, reader, writer),
+            Err(err) => warn!("tls handshake with {} failed: {}", peer, err),
+        });
+    }
+    Ok(())
+}
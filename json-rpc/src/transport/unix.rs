@@ -0,0 +1,34 @@
+use super::spawn_session;
+use crate::framing::Framing;
+use crate::server::Server;
+use log::warn;
+use std::fs;
+use std::io;
+use std::os::unix::net::UnixListener;
+use std::path::Path;
+
+/// Binds `path`, replacing a stale socket file left by a previous run.
+pub fn bind(path: &Path) -> io::Result<UnixListener> {
+    match fs::remove_file(path) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+    UnixListener::bind(path)
+}
+
+/// Accepts connections forever, serving each on its own thread.
+pub fn serve(listener: UnixListener, server: Server, framing: Framing) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!("unix accept failed: {}", err);
+                continue;
+            }
+        };
+        let writer = stream.try_clone()?;
+        spawn_session(server.clone(), framing, "unix".to_string(), stream, writer);
+    }
+    Ok(())
+}
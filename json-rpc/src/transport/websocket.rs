@@ -0,0 +1,141 @@
+//! WebSocket 传输：每条文本消息是一个 JSON-RPC 消息（或批量数组），
+//! 服务端可以随时推送通知。超过 [`Limits::max_message_size`] 的消息会让连接以
+//! 1009（Message Too Big）关闭。
+
+use crate::error::RpcError;
+use crate::message::Response;
+use crate::server::{Limits, Server, POLL_INTERVAL};
+use crate::session::Session;
+use log::{debug, info, warn};
+use serde_json::Value;
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use tungstenite::protocol::frame::coding::CloseCode;
+use tungstenite::protocol::{CloseFrame, WebSocketConfig};
+use tungstenite::{Message, WebSocket};
+
+fn io_error(err: tungstenite::Error) -> io::Error {
+    match err {
+        tungstenite::Error::Io(err) => err,
+        err => io::Error::other(err),
+    }
+}
+
+fn send(ws: &mut WebSocket<TcpStream>, message: &Value) -> io::Result<()> {
+    let text = serde_json::to_string(message)?;
+    debug!("ws send: {}", text);
+    ws.send(Message::text(text)).map_err(io_error)
+}
+
+fn handle_connection(
+    server: &Server,
+    mut ws: WebSocket<TcpStream>,
+    session: &Arc<Session>,
+    messages: Receiver<Value>,
+) -> io::Result<()> {
+    // 读操作最多阻塞一个轮询间隔，之后发送排队中的响应和通知
+    ws.get_ref().set_read_timeout(Some(POLL_INTERVAL))?;
+    let _busy = server.writer_busy();
+    loop {
+        let drained = server.is_drained();
+        while let Ok(message) = messages.try_recv() {
+            send(&mut ws, &message)?;
+        }
+        if drained || session.has_exited() {
+            // 对端可能已经先关闭了连接
+            let _ = ws.close(None);
+            let _ = ws.flush();
+            return Ok(());
+        }
+        let payload = match ws.read() {
+            Ok(Message::Text(text)) => text.into_bytes(),
+            Ok(Message::Binary(bytes)) => bytes,
+            Ok(_) => continue,
+            Err(tungstenite::Error::Io(err))
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                continue
+            }
+            Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                return Ok(())
+            }
+            Err(tungstenite::Error::Capacity(err)) => {
+                warn!("closing websocket: {}", err);
+                let _ = ws.close(Some(CloseFrame {
+                    code: CloseCode::Size,
+                    reason: "message too big".into(),
+                }));
+                let _ = ws.flush();
+                return Ok(());
+            }
+            Err(err) => return Err(io_error(err)),
+        };
+        let request = match serde_json::from_slice::<Value>(&payload) {
+            Ok(request) => request,
+            Err(err) => {
+                warn!("unparsable websocket message: {}", err);
+                let response = Response::failure(Value::Null, RpcError::parse_error(err));
+                send(&mut ws, &serde_json::to_value(response)?)?;
+                continue;
+            }
+        };
+        debug!("ws request: {}", request);
+        server.submit(session, request);
+    }
+}
+
+fn config(limits: Limits) -> WebSocketConfig {
+    WebSocketConfig {
+        max_message_size: Some(limits.max_message_size),
+        max_frame_size: Some(limits.max_message_size),
+        ..WebSocketConfig::default()
+    }
+}
+
+/// Accepts WebSocket upgrades forever, serving each connection on its own
+/// thread with its own [`Session`].
+pub fn serve(listener: TcpListener, server: Server) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!("websocket accept failed: {}", err);
+                continue;
+            }
+        };
+        if let Err(err) = stream.set_nodelay(true) {
+            warn!("websocket set_nodelay failed: {}", err);
+        }
+        let server = server.clone();
+        thread::spawn(move || {
+            let peer = stream
+                .peer_addr()
+                .map(|addr| addr.to_string())
+                .unwrap_or_default();
+            let ws = match tungstenite::accept_with_config(stream, Some(config(server.limits()))) {
+                Ok(ws) => ws,
+                Err(err) => {
+                    warn!("websocket handshake with {} failed: {}", peer, err);
+                    return;
+                }
+            };
+            let (outbound, messages) = mpsc::channel();
+            let session = Session::new(outbound);
+            info!("websocket session {} opened ({})", session.id(), peer);
+            let id = session.id();
+            let result = handle_connection(&server, ws, &session, messages);
+            session.calls().close();
+            match result {
+                Ok(()) => info!("websocket session {} closed", id),
+                Err(err) => warn!("websocket session {} failed: {}", id, err),
+            }
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use serde_json::Value;
+use std::process::Command;
+
+#[test]
+fn bench_reports_every_transport() {
+    let output = Command::new(env!("CARGO_BIN_EXE_bench"))
+        .args(["--server", env!("CARGO_BIN_EXE_server")])
+        .args(["--transport", "tcp,http,ws,stdio"])
+        .args(["--clients", "2", "--requests", "20", "--warmup", "2"])
+        .output()
+        .expect("failed to start bench");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["config"]["clients"], 2);
+    let transports = report["transports"].as_array().unwrap();
+    let names: Vec<&str> = transports
+        .iter()
+        .map(|t| t["transport"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["tcp", "http", "ws", "stdio"]);
+    for transport in transports {
+        assert_eq!(transport["requests"], 40);
+        assert_eq!(transport["errors"], 0);
+        let latency = &transport["latency_us"];
+        assert!(latency["p50"].as_u64() <= latency["p99"].as_u64());
+        // 每种调用都有自己的统计
+        let methods = transport["methods"].as_object().unwrap();
+        assert!(methods.contains_key("add") && methods.contains_key("echo-4k"));
+    }
+}
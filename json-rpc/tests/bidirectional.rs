@@ -0,0 +1,65 @@
+mod common;
+
+use json_rpc::{Client, Context, Framing, Router, RpcError, Server};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::thread;
+use std::time::Duration;
+
+fn start_server() -> SocketAddr {
+    let mut router = Router::new();
+    router
+        .register("double", |(n,): (i64,)| Ok::<_, RpcError>(n * 2))
+        .register_with_context("ask", |ctx: &Context, question: Value| {
+            ctx.session().call::<_, Value>("confirm", question)
+        })
+        .register_with_context("ask_quickly", |ctx: &Context, question: Value| {
+            ctx.session().call_with_timeout::<_, Value>(
+                "confirm",
+                question,
+                Duration::from_millis(50),
+            )
+        });
+    common::serve_tcp(Server::new(router), Framing::Newline)
+}
+
+#[test]
+fn server_calls_back_into_the_client() {
+    let client = Client::connect_tcp(start_server(), Framing::Newline).unwrap();
+    client.on_request("confirm", |ctx: &Context, question: Value| {
+        // 处理服务端请求时还可以再调用服务端，两个方向的 id 互不干扰
+        let doubled: i64 = ctx.session().call("double", [question["n"].clone()])?;
+        Ok::<_, RpcError>(json!({"answer": doubled}))
+    });
+
+    let answer: Value = client.call("ask", json!({"n": 21})).unwrap();
+    assert_eq!(answer, json!({"answer": 42}));
+    assert_eq!(client.call::<_, i64>("double", [4]).unwrap(), 8);
+    assert_eq!(client.pending(), 0);
+}
+
+#[test]
+fn unknown_client_methods_are_reported_to_the_server() {
+    let client = Client::connect_tcp(start_server(), Framing::Newline).unwrap();
+    let err = client.call::<_, Value>("ask", json!({})).unwrap_err();
+    assert_eq!(err.code, RpcError::METHOD_NOT_FOUND);
+}
+
+#[test]
+fn server_calls_can_time_out() {
+    let mut router = Router::new();
+    router.register_with_context("confirm", |ctx: &Context, _: Value| {
+        while !ctx.is_cancelled() {
+            thread::sleep(Duration::from_millis(5));
+        }
+        Ok::<_, RpcError>(true)
+    });
+    let client = Client::connect_tcp(start_server(), Framing::Newline)
+        .unwrap()
+        .with_router(router);
+
+    let err = client
+        .call::<_, Value>("ask_quickly", json!({}))
+        .unwrap_err();
+    assert_eq!(err.code, RpcError::REQUEST_TIMEOUT);
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use json_rpc::{Client, Framing, Router, RpcError, Server};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn matches_out_of_order_responses() {
+    // 故意把两个响应倒序发回
+    let addr = common::listen(|listener| {
+        let (stream, _) = listener.accept()?;
+        let mut writer = stream.try_clone().unwrap();
+        let mut lines = BufReader::new(stream).lines();
+        let first: Value = serde_json::from_str(&lines.next().unwrap().unwrap()).unwrap();
+        let second: Value = serde_json::from_str(&lines.next().unwrap().unwrap()).unwrap();
+        for request in [second, first] {
+            let response =
+                json!({"jsonrpc": "2.0", "id": request["id"], "result": request["params"]});
+            writeln!(writer, "{}", response)?;
+        }
+        Ok(())
+    });
+
+    let client = Arc::new(Client::connect_tcp(addr, Framing::Newline).unwrap());
+    let calls: Vec<_> = ["first", "second"]
+        .into_iter()
+        .map(|name| {
+            let client = Arc::clone(&client);
+            thread::spawn(move || client.call::<_, Vec<String>>("echo", [name]).unwrap())
+        })
+        .collect();
+    let results: Vec<Vec<String>> = calls.into_iter().map(|call| call.join().unwrap()).collect();
+    assert_eq!(results, [vec!["first"], vec!["second"]]);
+}
+
+#[test]
+fn typed_calls_against_a_tcp_server() {
+    let mut router = Router::new();
+    router
+        .register("add", |(a, b): (i64, i64)| Ok::<_, RpcError>(a + b))
+        .register("fail", |_: Value| -> Result<(), RpcError> {
+            Err(RpcError::new(7, "nope"))
+        });
+    let addr = common::serve_tcp(Server::new(router), Framing::ContentLength);
+
+    let client = Client::connect_tcp(addr, Framing::ContentLength).unwrap();
+    assert_eq!(client.call::<_, i64>("add", (2, 3)).unwrap(), 5);
+    assert_eq!(client.call::<_, ()>("fail", ()).unwrap_err().code, 7);
+    let err = client.call::<_, Value>("missing", ()).unwrap_err();
+    assert_eq!(err.code, RpcError::METHOD_NOT_FOUND);
+    client.notify("add", (1, 1)).unwrap();
+    client.close().unwrap();
+}
+
+#[test]
+fn shutdown_with_timeout_gives_up_on_a_busy_server() {
+    let mut router = Router::new();
+    // 不理会取消的慢请求：shutdown 要等它结束才会回复
+    router.register("sleep", |(ms,): (u64,)| {
+        thread::sleep(Duration::from_millis(ms));
+        Ok::<_, RpcError>(ms)
+    });
+    let addr = common::serve_tcp(Server::new(router), Framing::Newline);
+
+    let client = Client::connect_tcp(addr, Framing::Newline).unwrap();
+    let timeout = Duration::from_millis(50);
+    let err = client
+        .call_with_timeout::<_, u64>("sleep", [2000], timeout)
+        .unwrap_err();
+    assert_eq!(err.code, RpcError::REQUEST_TIMEOUT);
+
+    let started = Instant::now();
+    let err = client.shutdown_with_timeout(timeout).unwrap_err();
+    assert_eq!(err.code, RpcError::REQUEST_TIMEOUT);
+    assert!(started.elapsed() < Duration::from_millis(1000));
+}
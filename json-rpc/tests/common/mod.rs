@@ -0,0 +1,36 @@
+//! 集成测试共用的启动代码：在 127.0.0.1 的空闲端口上后台运行一个服务端。
+#![allow(dead_code)]
+
+use json_rpc::{transport, Client, Framing, Router, Server};
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+use std::thread;
+
+/// Binds a free port on 127.0.0.1 and runs `serve` on it in the background.
+pub fn listen<F>(serve: F) -> SocketAddr
+where
+    F: FnOnce(TcpListener) -> io::Result<()> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || serve(listener));
+    addr
+}
+
+pub fn serve_tcp(server: Server, framing: Framing) -> SocketAddr {
+    listen(move |listener| transport::tcp::serve(listener, server, framing))
+}
+
+pub fn serve_http(server: Server) -> SocketAddr {
+    listen(move |listener| transport::http::serve(listener, server))
+}
+
+pub fn serve_websocket(server: Server) -> SocketAddr {
+    listen(move |listener| transport::websocket::serve(listener, server))
+}
+
+/// Serves `router` over newline-framed TCP and connects a client to it.
+pub fn connect(router: Router) -> Client {
+    let addr = serve_tcp(Server::new(router), Framing::Newline);
+    Client::connect_tcp(addr, Framing::Newline).unwrap()
+}
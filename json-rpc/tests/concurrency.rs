@@ -0,0 +1,95 @@
+mod common;
+
+use json_rpc::{Client, Context, Framing, Router, RpcError, Server};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+fn start_server(started: mpsc::Sender<()>) -> SocketAddr {
+    let started = Mutex::new(started);
+    let mut router = Router::new();
+    router
+        .register("fast", |_: Value| Ok::<_, RpcError>("fast"))
+        .register_with_context("slow", move |ctx: &Context, _: Value| {
+            started.lock().unwrap().send(()).unwrap();
+            while !ctx.is_cancelled() {
+                thread::sleep(Duration::from_millis(5));
+            }
+            Ok::<_, RpcError>("slow")
+        });
+    common::serve_tcp(Server::with_workers(router, 2), Framing::Newline)
+}
+
+#[test]
+fn slow_request_does_not_block_others_and_can_be_cancelled() {
+    let (started, slow_running) = mpsc::channel();
+    let addr = start_server(started);
+    let client = Arc::new(Client::connect_tcp(addr, Framing::Newline).unwrap());
+
+    // 第一个请求的 id 是 1
+    let slow = {
+        let client = Arc::clone(&client);
+        thread::spawn(move || client.call::<_, String>("slow", ()))
+    };
+    slow_running.recv().unwrap();
+
+    assert_eq!(client.call::<_, String>("fast", ()).unwrap(), "fast");
+
+    client.notify("$/cancelRequest", json!({"id": 1})).unwrap();
+    let err = slow.join().unwrap().unwrap_err();
+    assert_eq!(err.code, RpcError::REQUEST_CANCELLED);
+}
+
+#[test]
+fn panicking_handler_gets_an_error_and_the_worker_survives() {
+    let mut router = Router::new();
+    router
+        .register("fast", |_: Value| Ok::<_, RpcError>("fast"))
+        .register("boom", |_: Value| -> Result<Value, RpcError> {
+            panic!("boom");
+        });
+    // 只有一个工作线程：它要是死了，后面的请求就没人处理
+    let addr = common::serve_tcp(Server::with_workers(router, 1), Framing::Newline);
+    let client = Client::connect_tcp(addr, Framing::Newline).unwrap();
+
+    for _ in 0..3 {
+        let err = client.call::<_, Value>("boom", ()).unwrap_err();
+        assert_eq!(err.code, RpcError::INTERNAL_ERROR);
+        assert_eq!(client.call::<_, String>("fast", ()).unwrap(), "fast");
+    }
+}
+
+#[test]
+fn malformed_message_does_not_release_a_running_request_id() {
+    let (started, slow_running) = mpsc::channel();
+    let addr = start_server(started);
+    let stream = TcpStream::connect(addr).unwrap();
+    // 回归时失败而不是一直挂着
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let mut writer = stream.try_clone().unwrap();
+    let mut lines = BufReader::new(stream).lines();
+
+    writeln!(writer, r#"{{"jsonrpc":"2.0","id":1,"method":"slow"}}"#).unwrap();
+    slow_running.recv().unwrap();
+    // 同一个 id、但 method 不是字符串
+    writeln!(writer, r#"{{"jsonrpc":"2.0","id":1,"method":5}}"#).unwrap();
+    let invalid: Value = serde_json::from_str(&lines.next().unwrap().unwrap()).unwrap();
+    assert_eq!(invalid["id"], 1);
+    assert_eq!(invalid["error"]["code"], RpcError::INVALID_REQUEST);
+
+    // 运行中的请求仍然可以取消
+    writeln!(
+        writer,
+        r#"{{"jsonrpc":"2.0","method":"$/cancelRequest","params":{{"id":1}}}}"#
+    )
+    .unwrap();
+    let cancelled: Value = serde_json::from_str(&lines.next().unwrap().unwrap()).unwrap();
+    assert_eq!(cancelled["id"], 1);
+    assert_eq!(cancelled["error"]["code"], RpcError::REQUEST_CANCELLED);
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use json_rpc::{rpc, Router, RpcError};
+use serde_json::json;
+
+#[rpc]
+pub trait Calculator {
+    /// Adds two numbers.
+    fn add(&self, a: f64, b: f64) -> f64;
+
+    #[rpc(name = "calc.div")]
+    fn div(&self, a: f64, b: f64) -> Result<f64, RpcError>;
+
+    fn version(&self) -> String;
+}
+
+struct Calc;
+
+impl Calculator for Calc {
+    fn add(&self, a: f64, b: f64) -> f64 {
+        a + b
+    }
+
+    fn div(&self, a: f64, b: f64) -> Result<f64, RpcError> {
+        if b == 0.0 {
+            return Err(RpcError::invalid_params("division by zero"));
+        }
+        Ok(a / b)
+    }
+
+    fn version(&self) -> String {
+        "1.0".to_string()
+    }
+}
+
+#[test]
+fn trait_methods_are_served_and_callable_through_the_stub() {
+    let mut router = Router::new();
+    Calc.register_rpc(&mut router);
+    let calculator = CalculatorClient::new(common::connect(router));
+    assert_eq!(calculator.add(1.5, 2.0).unwrap(), 3.5);
+    assert_eq!(calculator.div(1.0, 4.0).unwrap(), 0.25);
+    assert_eq!(
+        calculator.div(1.0, 0.0).unwrap_err().code,
+        RpcError::INVALID_PARAMS
+    );
+    assert_eq!(calculator.version().unwrap(), "1.0");
+
+    // 手写的 JSON 请求同样可以按名称或按位置传参
+    let client = calculator.client();
+    assert_eq!(
+        client
+            .call::<_, f64>("add", json!({"a": 1, "b": 2}))
+            .unwrap(),
+        3.0
+    );
+    assert_eq!(
+        client.call::<_, f64>("calc.div", json!([9, 3])).unwrap(),
+        3.0
+    );
+}
+
+#[test]
+fn doc_comments_become_descriptions() {
+    let mut router = Router::new();
+    Calc.register_rpc(&mut router);
+    let document = router.discover();
+    let add = &document["methods"][0];
+    assert_eq!(add["name"], "add");
+    assert_eq!(add["description"], "Adds two numbers.");
+    assert_eq!(router.method_names(), ["add", "calc.div", "version"]);
+}
+
+// 参数名和生成代码里的局部变量同名时也要能编译
+#[rpc]
+pub trait Directory {
+    fn lookup(&self, service: String, router: String) -> Result<String, json_rpc::RpcError>;
+}
+
+struct Names;
+
+impl Directory for Names {
+    fn lookup(&self, service: String, router: String) -> Result<String, json_rpc::RpcError> {
+        Ok(format!("{}@{}", service, router))
+    }
+}
+
+#[test]
+fn arguments_may_share_names_with_generated_locals() {
+    let mut router = Router::new();
+    Names.register_rpc(&mut router);
+    let directory = DirectoryClient::new(common::connect(router));
+    assert_eq!(
+        directory
+            .lookup("db".to_string(), "edge".to_string())
+            .unwrap(),
+        "db@edge"
+    );
+}
@@ -0,0 +1,53 @@
+use json_rpc::schemars::JsonSchema;
+use json_rpc::{Router, RpcError};
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Deserialize, JsonSchema)]
+struct Greet {
+    /// Who to greet
+    name: String,
+    #[serde(default)]
+    shout: bool,
+}
+
+#[test]
+fn document_lists_every_registered_method() {
+    let mut router = Router::new();
+    router
+        .register("greet", |p: Greet| {
+            Ok::<_, RpcError>(format!(
+                "hello {}{}",
+                p.name,
+                if p.shout { "!" } else { "" }
+            ))
+        })
+        .register("add", |(a, b): (i64, i64)| Ok::<_, RpcError>(a + b))
+        .register("ping", |(): ()| Ok::<_, RpcError>("pong"))
+        .describe("greet", "Greets someone.");
+
+    let document = router.discover();
+    assert_eq!(document["openrpc"], "1.2.6");
+    let methods = document["methods"].as_array().unwrap();
+    let names: Vec<&str> = methods
+        .iter()
+        .map(|m| m["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["add", "greet", "ping"]);
+
+    // 元组参数按位置描述
+    let add = &methods[0];
+    assert_eq!(add["paramStructure"], "by-position");
+    assert_eq!(add["params"][1]["name"], "arg1");
+    assert_eq!(add["result"]["schema"]["type"], "integer");
+
+    // 结构体字段是具名参数，文档注释成为描述
+    let greet = &methods[1];
+    assert_eq!(greet["description"], "Greets someone.");
+    assert_eq!(greet["params"][0]["name"], "name");
+    assert_eq!(greet["params"][0]["required"], true);
+    assert_eq!(greet["params"][0]["schema"]["description"], "Who to greet");
+    assert_eq!(greet["params"][1]["required"], false);
+
+    assert_eq!(methods[2]["params"], json!([]));
+}
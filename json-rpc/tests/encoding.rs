@@ -0,0 +1,102 @@
+mod common;
+
+use json_rpc::framing::{ContentLengthFramer, Framer};
+use json_rpc::{Client, Encoding, Framing, Router, RpcError, Server};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+
+fn router() -> Router {
+    let mut router = Router::new();
+    router.register("echo", |params: Value| Ok::<_, RpcError>(params));
+    router
+}
+
+fn start_tcp() -> SocketAddr {
+    common::serve_tcp(Server::new(router()), Framing::ContentLength)
+}
+
+fn sample() -> Value {
+    json!({"text": "héllo", "numbers": [1, -2, 3.5, u64::MAX], "nested": {"ok": true, "none": null}})
+}
+
+#[test]
+fn binary_encodings_round_trip_over_content_length_framing() {
+    let addr = start_tcp();
+    for encoding in [Encoding::Json, Encoding::MessagePack, Encoding::Cbor] {
+        let client = Client::connect_tcp(addr, Framing::ContentLength)
+            .unwrap()
+            .with_encoding(encoding);
+        let echoed: Value = client.call("echo", sample()).unwrap();
+        assert_eq!(echoed, sample(), "{}", encoding);
+        let err = client.call::<_, Value>("missing", ()).unwrap_err();
+        assert_eq!(err.code, RpcError::METHOD_NOT_FOUND, "{}", encoding);
+    }
+}
+
+#[test]
+fn server_replies_in_the_encoding_it_was_sent() {
+    let addr = start_tcp();
+    let mut stream = TcpStream::connect(addr).unwrap();
+    let framer = ContentLengthFramer;
+    let request = json!({"jsonrpc": "2.0", "id": 1, "method": "echo", "params": [1, 2]});
+    let payload = Encoding::Cbor.encode(&request).unwrap();
+    framer
+        .write_typed_frame(&mut stream, &payload, "application/cbor")
+        .unwrap();
+
+    let mut reader = BufReader::new(stream);
+    let (frame, content_type) = framer.read_typed_frame(&mut reader).unwrap().unwrap();
+    assert_eq!(content_type.as_deref(), Some("application/cbor"));
+    let response = Encoding::Cbor.decode(&frame).unwrap();
+    assert_eq!(response["result"], json!([1, 2]));
+}
+
+#[test]
+fn newline_framing_refuses_binary_encodings() {
+    let addr = common::serve_tcp(Server::new(router()), Framing::Newline);
+    let client = Client::connect_tcp(addr, Framing::Newline)
+        .unwrap()
+        .with_encoding(Encoding::MessagePack);
+    let err = client.call::<_, Value>("echo", [1]).unwrap_err();
+    assert_eq!(err.code, RpcError::TRANSPORT_ERROR);
+}
+
+#[test]
+fn http_accepts_messagepack_bodies() {
+    let addr = common::serve_http(Server::new(router()));
+
+    let request = json!({"jsonrpc": "2.0", "id": 7, "method": "echo", "params": sample()});
+    let body = Encoding::MessagePack.encode(&request).unwrap();
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "POST /rpc HTTP/1.1\r\nHost: {}\r\nContent-Type: application/msgpack\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        addr,
+        body.len()
+    )
+    .unwrap();
+    stream.write_all(&body).unwrap();
+
+    let mut reader = BufReader::new(stream);
+    let mut content_type = None;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-type") {
+                content_type = Some(value.trim().to_string());
+            }
+        }
+    }
+    assert_eq!(content_type.as_deref(), Some("application/msgpack"));
+    let mut body = Vec::new();
+    reader.read_to_end(&mut body).unwrap();
+    let response = Encoding::MessagePack.decode(&body).unwrap();
+    assert_eq!(response["id"], 7);
+    assert_eq!(response["result"], sample());
+}
@@ -0,0 +1,167 @@
+use json_rpc::server::Limits;
+use json_rpc::{Encoding, Framing, Router, RpcError, Server};
+use serde_json::Value;
+use std::io::Cursor;
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// xorshift64*：测试只需要可复现的伪随机数，不需要额外依赖。
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// Up to `max` random bytes.
+    fn bytes(&mut self, max: usize) -> Vec<u8> {
+        let len = self.below(max + 1);
+        (0..len).map(|_| self.next() as u8).collect()
+    }
+}
+
+const SAMPLES: &[&str] = &[
+    r#"{"jsonrpc":"2.0","id":1,"method":"echo","params":[1,"two",{"three":3}]}"#,
+    r#"[{"jsonrpc":"2.0","id":"a","method":"echo"},{"jsonrpc":"2.0","method":"echo"}]"#,
+    r#"{"jsonrpc":"2.0","method":"$/cancelRequest","params":{"id":1}}"#,
+    r#"{"jsonrpc":"2.0","id":2,"result":null}"#,
+    r#"{"jsonrpc":"2.0","id":3,"method":"shutdown"}"#,
+];
+
+const CONTENT_TYPES: &[&str] = &[
+    "application/json",
+    "application/msgpack",
+    "application/cbor",
+    "text/plain",
+];
+
+/// A valid message with a few bytes flipped, inserted or removed.
+fn mutated(rng: &mut Rng) -> Vec<u8> {
+    let mut message = SAMPLES[rng.below(SAMPLES.len())].as_bytes().to_vec();
+    for _ in 0..=rng.below(4) {
+        let at = rng.below(message.len() + 1);
+        match rng.below(3) {
+            0 if at < message.len() => message[at] = rng.next() as u8,
+            1 => message.insert(at, rng.next() as u8),
+            _ if at < message.len() => {
+                message.remove(at);
+            }
+            _ => {}
+        }
+    }
+    message
+}
+
+fn payload(rng: &mut Rng) -> Vec<u8> {
+    match rng.below(3) {
+        0 => rng.bytes(256),
+        1 => mutated(rng),
+        // 超过长度限制或嵌套过深
+        _ => {
+            let depth = rng.below(200);
+            let mut nested = "[".repeat(depth).into_bytes();
+            nested.extend(rng.bytes(8));
+            nested.extend("]".repeat(depth).into_bytes());
+            nested
+        }
+    }
+}
+
+fn newline_input(rng: &mut Rng) -> Vec<u8> {
+    let mut input = Vec::new();
+    for _ in 0..rng.below(6) {
+        input.extend(payload(rng));
+        input.push(b'\n');
+    }
+    input
+}
+
+fn content_length_input(rng: &mut Rng) -> Vec<u8> {
+    let mut input = Vec::new();
+    for _ in 0..rng.below(6) {
+        let body = payload(rng);
+        // 偶尔谎报长度，或者在头部混入随机字节
+        let length = match rng.below(8) {
+            0 => rng.below(4096),
+            _ => body.len(),
+        };
+        input.extend(format!("Content-Length: {}\r\n", length).into_bytes());
+        if rng.below(2) == 0 {
+            let content_type = CONTENT_TYPES[rng.below(CONTENT_TYPES.len())];
+            input.extend(format!("Content-Type: {}\r\n", content_type).into_bytes());
+        }
+        if rng.below(10) == 0 {
+            input.extend(rng.bytes(32));
+        }
+        input.extend(b"\r\n");
+        input.extend(body);
+    }
+    input
+}
+
+/// Whatever the input, every message the server writes is JSON-RPC 2.0.
+fn assert_valid(message: &Value) {
+    let first = message.as_array().map_or(message, |batch| &batch[0]);
+    assert_eq!(first["jsonrpc"], "2.0", "{}", message);
+}
+
+#[test]
+fn random_input_never_panics() {
+    let panics = Arc::new(AtomicUsize::new(0));
+    // 记下原来的钩子，测试结束后装回去
+    let previous = Arc::new(panic::take_hook());
+    {
+        let (panics, previous) = (Arc::clone(&panics), Arc::clone(&previous));
+        panic::set_hook(Box::new(move |info| {
+            panics.fetch_add(1, Ordering::SeqCst);
+            (*previous)(info);
+        }));
+    }
+
+    let mut router = Router::new();
+    router.register("echo", |params: Value| Ok::<_, RpcError>(params));
+    let server = Server::with_workers(router, 2).with_limits(Limits {
+        max_message_size: 512,
+        max_depth: 16,
+    });
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+
+    for _ in 0..1000 {
+        let input = newline_input(&mut rng);
+        let mut output = Vec::new();
+        let framer = Framing::Newline.framer();
+        let _ = server.serve(&*framer, Cursor::new(&input), &mut output);
+        for line in output
+            .split(|&byte| byte == b'\n')
+            .filter(|line| !line.is_empty())
+        {
+            assert_valid(&serde_json::from_slice(line).unwrap());
+        }
+    }
+    for _ in 0..1000 {
+        let input = content_length_input(&mut rng);
+        let mut output = Vec::new();
+        let framer = Framing::ContentLength.framer();
+        let _ = server.serve(&*framer, Cursor::new(&input), &mut output);
+        let mut reader = Cursor::new(output);
+        // 响应使用请求的编码，按 Content-Type 解码
+        while let Some((frame, content_type)) = framer.read_typed_frame(&mut reader).unwrap() {
+            let encoding = content_type
+                .and_then(|content_type| Encoding::from_content_type(&content_type))
+                .unwrap_or(Encoding::Json);
+            assert_valid(&encoding.decode(&frame).unwrap());
+        }
+    }
+
+    let _ = panic::take_hook();
+    panic::set_hook(Box::new(move |info| (*previous)(info)));
+    assert_eq!(panics.load(Ordering::SeqCst), 0);
+}
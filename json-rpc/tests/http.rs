@@ -0,0 +1,220 @@
+mod common;
+
+use json_rpc::middleware::RateLimit;
+use json_rpc::{Context, Router, RpcError, Server};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream};
+
+fn echo_router() -> Router {
+    let mut router = Router::new();
+    router
+        .register("echo", |params: Value| Ok::<_, RpcError>(params))
+        .register("add", |(a, b): (i64, i64)| Ok::<_, RpcError>(a + b))
+        .register_with_context("ask", |ctx: &Context, question: Value| {
+            ctx.session().call::<_, Value>("confirm", question)
+        });
+    router
+}
+
+fn start_server() -> SocketAddr {
+    serve(echo_router())
+}
+
+fn serve(router: Router) -> SocketAddr {
+    common::serve_http(Server::new(router))
+}
+
+struct HttpReply {
+    status: u16,
+    content_type: Option<String>,
+    body: Vec<u8>,
+}
+
+fn write_request<W: Write>(
+    writer: &mut W,
+    addr: SocketAddr,
+    method: &str,
+    path: &str,
+    body: &str,
+    keep_alive: bool,
+) {
+    let connection = if keep_alive { "keep-alive" } else { "close" };
+    write!(
+        writer,
+        "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: {}\r\n\r\n{}",
+        method,
+        path,
+        addr,
+        body.len(),
+        connection,
+        body
+    )
+    .unwrap();
+}
+
+fn read_reply<R: BufRead>(reader: &mut R) -> HttpReply {
+    let mut status_line = String::new();
+    reader.read_line(&mut status_line).unwrap();
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap()
+        .parse()
+        .unwrap();
+    let mut content_type = None;
+    let mut length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line.split_once(':').unwrap();
+        if name.eq_ignore_ascii_case("content-type") {
+            content_type = Some(value.trim().to_string());
+        } else if name.eq_ignore_ascii_case("content-length") {
+            length = value.trim().parse().unwrap();
+        }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+    HttpReply {
+        status,
+        content_type,
+        body,
+    }
+}
+
+// 简单的测试客户端：每次请求都带 `Connection: close`
+fn request(addr: SocketAddr, method: &str, path: &str, body: &str) -> HttpReply {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write_request(&mut stream, addr, method, path, body, false);
+    read_reply(&mut BufReader::new(stream))
+}
+
+#[test]
+fn post_single_request() {
+    let addr = start_server();
+    let reply = request(
+        addr,
+        "POST",
+        "/rpc",
+        r#"{"jsonrpc":"2.0","id":1,"method":"add","params":[2,3]}"#,
+    );
+    assert_eq!(reply.status, 200);
+    assert_eq!(reply.content_type.as_deref(), Some("application/json"));
+    let body: Value = serde_json::from_slice(&reply.body).unwrap();
+    assert_eq!(body, json!({"jsonrpc": "2.0", "id": 1, "result": 5}));
+}
+
+#[test]
+fn post_batch_request() {
+    let addr = start_server();
+    let batch = json!([
+        {"jsonrpc": "2.0", "id": 1, "method": "echo", "params": ["a"]},
+        {"jsonrpc": "2.0", "method": "echo", "params": ["ignored"]},
+        {"jsonrpc": "2.0", "id": 2, "method": "missing"},
+    ]);
+    let reply = request(addr, "POST", "/rpc", &batch.to_string());
+    assert_eq!(reply.status, 200);
+    let body: Value = serde_json::from_slice(&reply.body).unwrap();
+    let responses = body.as_array().unwrap();
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0]["result"], json!(["a"]));
+    assert_eq!(responses[1]["error"]["code"], RpcError::METHOD_NOT_FOUND);
+}
+
+#[test]
+fn null_id_is_a_request_not_a_notification() {
+    let addr = start_server();
+    let reply = request(
+        addr,
+        "POST",
+        "/rpc",
+        r#"{"jsonrpc":"2.0","id":null,"method":"add","params":[2,3]}"#,
+    );
+    assert_eq!(reply.status, 200);
+    let body: Value = serde_json::from_slice(&reply.body).unwrap();
+    assert_eq!(body, json!({"jsonrpc": "2.0", "id": null, "result": 5}));
+
+    let request: json_rpc::Request =
+        serde_json::from_value(json!({"jsonrpc": "2.0", "id": null, "method": "add"})).unwrap();
+    assert_eq!(request.id, Some(Value::Null));
+    assert!(!request.is_notification());
+    let notification: json_rpc::Request =
+        serde_json::from_value(json!({"jsonrpc": "2.0", "method": "add"})).unwrap();
+    assert!(notification.is_notification());
+}
+
+#[test]
+fn notifications_only_return_no_content() {
+    let addr = start_server();
+    let reply = request(
+        addr,
+        "POST",
+        "/rpc",
+        r#"{"jsonrpc":"2.0","method":"echo","params":[]}"#,
+    );
+    assert_eq!(reply.status, 204);
+    assert!(reply.body.is_empty());
+}
+
+#[test]
+fn status_codes_for_bad_requests() {
+    let addr = start_server();
+    assert_eq!(request(addr, "POST", "/other", "{}").status, 404);
+    assert_eq!(request(addr, "GET", "/rpc", "").status, 405);
+
+    let reply = request(addr, "POST", "/rpc", "{not json");
+    assert_eq!(reply.status, 400);
+    let body: Value = serde_json::from_slice(&reply.body).unwrap();
+    assert_eq!(body["error"]["code"], RpcError::PARSE_ERROR);
+}
+
+#[test]
+fn calls_to_the_client_fail_instead_of_hanging() {
+    let addr = start_server();
+    // HTTP 客户端收不到服务端发起的请求，`ask` 应该马上得到传输错误
+    let reply = request(
+        addr,
+        "POST",
+        "/rpc",
+        r#"{"jsonrpc":"2.0","id":1,"method":"ask","params":["sure?"]}"#,
+    );
+    assert_eq!(reply.status, 200);
+    let body: Value = serde_json::from_slice(&reply.body).unwrap();
+    assert_eq!(body["id"], 1);
+    assert_eq!(body["error"]["code"], RpcError::TRANSPORT_ERROR);
+}
+
+#[test]
+fn rate_limit_applies_per_connection() {
+    let mut router = echo_router();
+    router.layer(RateLimit::new(0.001, 2));
+    let addr = serve(router);
+    let body = r#"{"jsonrpc":"2.0","id":1,"method":"echo","params":[1]}"#;
+
+    // 同一个 keep-alive 连接上的请求共用一个配额
+    let stream = TcpStream::connect(addr).unwrap();
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut codes = Vec::new();
+    for _ in 0..3 {
+        write_request(&mut writer, addr, "POST", "/rpc", body, true);
+        let reply = read_reply(&mut reader);
+        assert_eq!(reply.status, 200);
+        let body: Value = serde_json::from_slice(&reply.body).unwrap();
+        codes.push(body["error"]["code"].clone());
+    }
+    assert_eq!(
+        codes,
+        [Value::Null, Value::Null, json!(RpcError::RATE_LIMITED)]
+    );
+
+    // 新连接有自己的配额
+    let reply = request(addr, "POST", "/rpc", body);
+    let body: Value = serde_json::from_slice(&reply.body).unwrap();
+    assert_eq!(body["result"], json!([1]));
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use json_rpc::{Client, Framing, Router, RpcError, Server};
+use serde_json::Value;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+fn spawn_server() -> Client {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_server"));
+    command.env_remove("RPC_LOG_FILE").stderr(Stdio::null());
+    Client::spawn(&mut command, Framing::Newline).unwrap()
+}
+
+#[test]
+fn orderly_shutdown_exits_with_success() {
+    let client = spawn_server();
+    let info = client.initialize().unwrap();
+    assert_eq!(info["serverInfo"]["name"], "json-rpc");
+    assert!(info["capabilities"]["methods"]
+        .as_array()
+        .unwrap()
+        .contains(&Value::from("echo")));
+
+    let status = client.shutdown().unwrap().unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn exit_without_shutdown_is_an_error() {
+    let client = spawn_server();
+    client.notify("exit", ()).unwrap();
+    let status = client.close().unwrap().unwrap();
+    assert_eq!(status.code(), Some(1));
+}
+
+#[test]
+fn requests_after_shutdown_are_rejected() {
+    let client = spawn_server();
+    assert_eq!(
+        client.call::<_, Value>("shutdown", ()).unwrap(),
+        Value::Null
+    );
+    let err = client.call::<_, Value>("echo", [1]).unwrap_err();
+    assert_eq!(err.code, RpcError::INVALID_REQUEST);
+    client.notify("exit", ()).unwrap();
+    assert!(client.close().unwrap().unwrap().success());
+}
+
+// shutdown 要等同一连接上进行中的请求完成后才回复
+#[test]
+fn shutdown_waits_for_in_flight_requests() {
+    // 处理函数返回前记下完成的次数，不依赖客户端线程的调度
+    let finished = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&finished);
+    let mut router = Router::new();
+    router.register("sleep", move |(ms,): (u64,)| {
+        thread::sleep(Duration::from_millis(ms));
+        counter.fetch_add(1, Ordering::SeqCst);
+        Ok::<_, RpcError>(ms)
+    });
+    let server = Server::new(router);
+    let addr = common::serve_tcp(server.clone(), Framing::Newline);
+
+    let client = Client::connect_tcp(addr, Framing::Newline).unwrap();
+    thread::scope(|scope| {
+        let slow = scope.spawn(|| client.call::<_, u64>("sleep", [200]));
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(
+            client.call::<_, Value>("shutdown", ()).unwrap(),
+            Value::Null
+        );
+        // 回复 shutdown 时慢请求已经结束
+        assert_eq!(finished.load(Ordering::SeqCst), 1);
+        assert_eq!(slow.join().unwrap().unwrap(), 200);
+    });
+
+    // 服务端整体排空：进行中的请求照常完成，新请求被拒绝
+    let other = Client::connect_tcp(addr, Framing::Newline).unwrap();
+    thread::scope(|scope| {
+        let slow = scope.spawn(|| other.call::<_, u64>("sleep", [200]));
+        thread::sleep(Duration::from_millis(50));
+        let draining = scope.spawn(|| server.shutdown(Duration::from_secs(5)));
+        thread::sleep(Duration::from_millis(50));
+        let err = other.call::<_, Value>("sleep", [0]).unwrap_err();
+        assert_eq!(err.code, RpcError::INVALID_REQUEST);
+        assert_eq!(slow.join().unwrap().unwrap(), 200);
+        assert!(draining.join().unwrap());
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn sigterm_drains_and_exits() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_server"))
+        .args(["--tcp", "127.0.0.1:0"])
+        .env_remove("RPC_LOG_FILE")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    thread::sleep(Duration::from_millis(300));
+    let killed = Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+    assert!(child.wait().unwrap().success());
+}
@@ -0,0 +1,76 @@
+use json_rpc::server::Limits;
+use json_rpc::{Framing, Router, RpcError, Server};
+use serde_json::{json, Value};
+use std::io::Cursor;
+
+fn server() -> Server {
+    let mut router = Router::new();
+    router.register("echo", |params: Value| Ok::<_, RpcError>(params));
+    Server::new(router).with_limits(Limits {
+        max_message_size: 64,
+        max_depth: 4,
+    })
+}
+
+fn run(framing: Framing, input: &[u8]) -> Vec<Value> {
+    let mut output = Vec::new();
+    server()
+        .serve(&*framing.framer(), Cursor::new(input), &mut output)
+        .unwrap();
+    let mut reader = Cursor::new(output);
+    let framer = framing.framer();
+    let mut messages = Vec::new();
+    while let Some(frame) = framer.read_frame(&mut reader).unwrap() {
+        messages.push(serde_json::from_slice(&frame).unwrap());
+    }
+    messages
+}
+
+fn error_for(messages: &[Value], id: Value) -> i64 {
+    let message = messages.iter().find(|message| message["id"] == id).unwrap();
+    message["error"]["code"].as_i64().unwrap()
+}
+
+#[test]
+fn oversized_lines_are_skipped_and_answered() {
+    let mut input = format!("[{}]\n", "1,".repeat(100)).into_bytes();
+    input.extend(b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"echo\",\"params\":[1]}\n");
+    let messages = run(Framing::Newline, &input);
+
+    assert_eq!(messages.len(), 2);
+    assert_eq!(error_for(&messages, Value::Null), RpcError::INVALID_REQUEST);
+    assert!(messages.contains(&json!({"jsonrpc": "2.0", "id": 1, "result": [1]})));
+}
+
+#[test]
+fn oversized_bodies_are_skipped_and_answered() {
+    let big = format!("[{}1]", "1,".repeat(100));
+    let small = r#"{"jsonrpc":"2.0","id":1,"method":"echo","params":[1]}"#;
+    let input = format!(
+        "Content-Length: {}\r\n\r\n{}Content-Length: {}\r\n\r\n{}",
+        big.len(),
+        big,
+        small.len(),
+        small
+    );
+    let messages = run(Framing::ContentLength, input.as_bytes());
+
+    assert_eq!(messages.len(), 2);
+    assert_eq!(error_for(&messages, Value::Null), RpcError::INVALID_REQUEST);
+    assert!(messages.contains(&json!({"jsonrpc": "2.0", "id": 1, "result": [1]})));
+}
+
+#[test]
+fn malformed_and_deep_messages_get_error_responses() {
+    let input = concat!(
+        "{\"jsonrpc\": \"2.0\", \"id\": 1,\n",
+        "{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"echo\",\"params\":[[[[[1]]]]]}\n",
+        "{\"jsonrpc\":\"2.0\",\"id\":3,\"method\":\"echo\",\"params\":[[1]]}\n",
+    );
+    let messages = run(Framing::Newline, input.as_bytes());
+
+    assert_eq!(messages.len(), 3);
+    assert_eq!(error_for(&messages, Value::Null), RpcError::PARSE_ERROR);
+    assert_eq!(error_for(&messages, json!(2)), RpcError::INVALID_REQUEST);
+    assert!(messages.contains(&json!({"jsonrpc": "2.0", "id": 3, "result": [[1]]})));
+}
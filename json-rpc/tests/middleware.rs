@@ -0,0 +1,86 @@
+mod common;
+
+use json_rpc::middleware::{Auth, Metrics, RateLimit, RequestLog};
+use json_rpc::{Client, Framing, Router, RpcError, Server};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+fn echo_router() -> Router {
+    let mut router = Router::new();
+    router.register("echo", |params: Value| Ok::<_, RpcError>(params));
+    router
+}
+
+#[test]
+fn auth_rejects_requests_without_a_valid_token() {
+    let mut router = echo_router();
+    router.layer(Auth::new(["secret"]).allow("rpc.methods"));
+    let client = common::connect(router);
+
+    let err = client.call::<_, Value>("echo", [1]).unwrap_err();
+    assert_eq!(err.code, RpcError::UNAUTHORIZED);
+    // 白名单方法不需要 token
+    assert!(client.call::<_, Vec<String>>("rpc.methods", ()).is_ok());
+
+    client.set_token("wrong");
+    let err = client.call::<_, Value>("echo", [1]).unwrap_err();
+    assert_eq!(err.code, RpcError::UNAUTHORIZED);
+
+    client.set_token("secret");
+    assert_eq!(client.call::<_, Value>("echo", [1]).unwrap(), json!([1]));
+}
+
+#[test]
+fn rate_limit_is_per_connection() {
+    let mut router = echo_router();
+    router.layer(RateLimit::new(0.001, 2));
+    let addr = common::serve_tcp(Server::new(router), Framing::Newline);
+    let first = Client::connect_tcp(addr, Framing::Newline).unwrap();
+    let second = Client::connect_tcp(addr, Framing::Newline).unwrap();
+
+    assert!(first.call::<_, Value>("echo", [1]).is_ok());
+    assert!(first.call::<_, Value>("echo", [2]).is_ok());
+    let err = first.call::<_, Value>("echo", [3]).unwrap_err();
+    assert_eq!(err.code, RpcError::RATE_LIMITED);
+
+    // 另一个连接有自己的配额
+    assert!(second.call::<_, Value>("echo", [1]).is_ok());
+}
+
+#[test]
+fn layers_wrap_dispatch_in_order_and_count_calls() {
+    let metrics = Metrics::new();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut router = echo_router();
+    metrics.register(&mut router);
+    let seen = Arc::clone(&calls);
+    router.layer(RequestLog).layer(Arc::clone(&metrics)).layer(
+        move |ctx: &_, request: &json_rpc::Request, next: json_rpc::middleware::Next<'_>| {
+            seen.fetch_add(1, Ordering::SeqCst);
+            if request.method == "blocked" {
+                return Err(RpcError::new(-1, "blocked by middleware"));
+            }
+            next.run(ctx, request)
+        },
+    );
+    let client = common::connect(router);
+
+    assert_eq!(client.call::<_, Value>("echo", [1]).unwrap(), json!([1]));
+    assert_eq!(client.call::<_, Value>("echo", [2]).unwrap(), json!([2]));
+    assert_eq!(client.call::<_, Value>("blocked", ()).unwrap_err().code, -1);
+    assert_eq!(
+        client.call::<_, Value>("missing", ()).unwrap_err().code,
+        RpcError::METHOD_NOT_FOUND
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+    let snapshot: Value = client.call("rpc.metrics", ()).unwrap();
+    assert_eq!(snapshot["echo"]["calls"], 2);
+    assert_eq!(snapshot["echo"]["errors"], 0);
+    // 没有注册的方法都记在同一项下
+    assert_eq!(snapshot[Metrics::UNKNOWN]["calls"], 2);
+    assert_eq!(snapshot[Metrics::UNKNOWN]["errors"], 2);
+    assert!(snapshot.get("blocked").is_none());
+    assert!(snapshot.get("missing").is_none());
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use json_rpc::client::Update;
+use json_rpc::{Client, Context, Framing, Router, RpcError, Server};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+
+fn start_server() -> SocketAddr {
+    let mut router = Router::new();
+    router.register_with_context("steps", |ctx: &Context, (n,): (u32,)| {
+        for step in 1..=n {
+            assert!(ctx.progress(json!({"step": step})));
+        }
+        Ok::<_, RpcError>(n)
+    });
+    common::serve_tcp(Server::new(router), Framing::Newline)
+}
+
+#[test]
+fn progress_arrives_before_the_result() {
+    let client = Client::connect_tcp(start_server(), Framing::Newline).unwrap();
+    let updates: Vec<Update> = client.call_streaming("steps", [3]).unwrap().collect();
+    assert_eq!(
+        updates,
+        vec![
+            Update::Progress(json!({"step": 1})),
+            Update::Progress(json!({"step": 2})),
+            Update::Progress(json!({"step": 3})),
+            Update::Done(Ok(json!(3))),
+        ]
+    );
+    assert_eq!(client.pending(), 0);
+}
+
+#[test]
+fn plain_calls_skip_progress() {
+    let client = Client::connect_tcp(start_server(), Framing::Newline).unwrap();
+    assert_eq!(client.call::<_, u32>("steps", [2]).unwrap(), 2);
+    let streaming = client.call_streaming("steps", [2]).unwrap();
+    assert_eq!(streaming.finish::<u32>().unwrap(), 2);
+
+    let err = client
+        .call_streaming("missing", Value::Null)
+        .unwrap()
+        .finish::<Value>()
+        .unwrap_err();
+    assert_eq!(err.code, RpcError::METHOD_NOT_FOUND);
+}
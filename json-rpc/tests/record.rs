@@ -0,0 +1,96 @@
+mod common;
+
+use json_rpc::record::{Exchange, Recorder, Replay};
+use json_rpc::{Router, RpcError};
+use serde_json::{json, Value};
+use std::fs;
+use std::io::BufReader;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+#[test]
+fn replay_serves_recorded_responses_without_a_backend() {
+    let path = std::env::temp_dir().join(format!("json-rpc-record-{}.jsonl", std::process::id()));
+
+    // 录制：真实的后端，计数器每次调用加一
+    let counter = Arc::new(AtomicU64::new(0));
+    let mut router = Router::new();
+    router
+        .register("add", |(a, b): (i64, i64)| Ok::<_, RpcError>(a + b))
+        .register("next", move |(): ()| {
+            Ok::<_, RpcError>(counter.fetch_add(1, Ordering::SeqCst) + 1)
+        })
+        .layer(Recorder::create(&path).unwrap());
+    let client = common::connect(router);
+    assert_eq!(client.call::<_, i64>("add", [1, 2]).unwrap(), 3);
+    assert_eq!(client.call::<_, u64>("next", ()).unwrap(), 1);
+    assert_eq!(client.call::<_, u64>("next", ()).unwrap(), 2);
+    let err = client
+        .call::<_, Value>("missing", json!({"x": 1}))
+        .unwrap_err();
+    client.notify("add", [5, 5]).unwrap();
+    client.close().unwrap();
+
+    let exchanges: Vec<Exchange> = fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    // 通知不录制
+    assert_eq!(exchanges.len(), 4);
+    assert_eq!(exchanges[0].method, "add");
+    assert_eq!(exchanges[0].result, Some(json!(3)));
+    assert!(exchanges[0].timestamp_ms > 0);
+    assert_eq!(exchanges[3].error.as_ref().unwrap().code, err.code);
+
+    // 回放：没有注册任何方法
+    let mut router = Router::new();
+    let replay = Replay::from_reader(BufReader::new(fs::File::open(&path).unwrap())).unwrap();
+    router.layer(replay);
+    let client = common::connect(router);
+    assert_eq!(client.call::<_, i64>("add", [1, 2]).unwrap(), 3);
+    assert_eq!(client.call::<_, u64>("next", ()).unwrap(), 1);
+    assert_eq!(client.call::<_, u64>("next", ()).unwrap(), 2);
+    // 录制的结果用完后重复最后一个
+    assert_eq!(client.call::<_, u64>("next", ()).unwrap(), 2);
+    let replayed = client
+        .call::<_, Value>("missing", json!({"x": 1}))
+        .unwrap_err();
+    assert_eq!(replayed, err);
+
+    let err = client.call::<_, i64>("add", [2, 2]).unwrap_err();
+    assert_eq!(err.code, RpcError::NOT_RECORDED);
+
+    // 内置的 rpc.* 方法照常由服务端回答
+    let methods: Vec<String> = client.call("rpc.methods", ()).unwrap();
+    assert!(methods.is_empty());
+    let document: Value = client.call("rpc.discover", ()).unwrap();
+    assert!(document["methods"].as_array().unwrap().is_empty());
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn replay_ignores_member_order() {
+    let path = std::env::temp_dir().join(format!(
+        "json-rpc-record-order-{}.jsonl",
+        std::process::id()
+    ));
+    let mut router = Router::new();
+    router
+        .register("sum", |p: Value| {
+            Ok::<_, RpcError>(p["a"].as_i64().unwrap_or(0) + p["b"].as_i64().unwrap_or(0))
+        })
+        .layer(Recorder::create(&path).unwrap());
+    let client = common::connect(router);
+    let params: Value = serde_json::from_str(r#"{"a":1,"b":2}"#).unwrap();
+    assert_eq!(client.call::<_, i64>("sum", params).unwrap(), 3);
+    client.close().unwrap();
+
+    let mut router = Router::new();
+    router.layer(Replay::load(&path).unwrap());
+    let client = common::connect(router);
+    // 成员顺序不同，仍然是同一个请求
+    let params: Value = serde_json::from_str(r#"{"b":2,"a":1}"#).unwrap();
+    assert_eq!(client.call::<_, i64>("sum", params).unwrap(), 3);
+    let _ = fs::remove_file(&path);
+}
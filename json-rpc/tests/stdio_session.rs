@@ -0,0 +1,62 @@
+use serde_json::{json, Value};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// 回放一段会话，确保 stdout 上的每一行都是合法的 JSON-RPC 响应。
+#[test]
+fn stdout_carries_only_protocol_messages() {
+    let session = [
+        json!({"jsonrpc": "2.0", "id": 1, "method": "echo", "params": {"text": "hi"}}),
+        json!({"jsonrpc": "2.0", "id": 2, "method": "add", "params": [1, 2]}),
+        json!({"jsonrpc": "2.0", "id": 3, "method": "add", "params": {"a": 1}}),
+        json!({"jsonrpc": "2.0", "method": "echo", "params": ["notification"]}),
+        json!({"jsonrpc": "2.0", "id": 4, "method": "missing"}),
+    ];
+    let mut input = String::new();
+    for message in &session {
+        input.push_str(&message.to_string());
+        input.push('\n');
+    }
+    input.push_str("not json at all\n");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_server"))
+        .env("RPC_LOG", "trace")
+        .env_remove("RPC_LOG_FILE")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to start server");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let responses: Vec<Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|_| panic!("not JSON: {}", line)))
+        .collect();
+    for response in &responses {
+        assert_eq!(response["jsonrpc"], "2.0");
+        assert!(response.get("id").is_some());
+        assert!(response.get("result").is_some() ^ response.get("error").is_some());
+    }
+    // 无法解析的那一行得到 id 为 null 的 Parse error
+    let parse_error = responses.iter().find(|r| r["id"].is_null()).unwrap();
+    assert_eq!(parse_error["error"]["code"], -32700);
+    // 请求并发执行，响应顺序不固定
+    let mut ids: Vec<i64> = responses.iter().filter_map(|r| r["id"].as_i64()).collect();
+    ids.sort();
+    assert_eq!(ids, [1, 2, 3, 4]);
+    let add = responses.iter().find(|r| r["id"] == 2).unwrap();
+    assert_eq!(add["result"], 3);
+
+    // trace 级别的日志应该出现在 stderr 上
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("request:"));
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use json_rpc::{Context, Router, RpcError};
+use serde_json::Value;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 故意很慢的方法：睡眠指定毫秒数，被取消时提前返回
+fn slow(ctx: &Context, millis: u64) -> Result<u64, RpcError> {
+    let deadline = Instant::now() + Duration::from_millis(millis);
+    while Instant::now() < deadline {
+        if ctx.is_cancelled() {
+            return Err(RpcError::request_cancelled());
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    Ok(millis)
+}
+
+#[test]
+fn per_method_timeout_overrides_global_timeout() {
+    let mut router = Router::new();
+    router
+        .register_with_context("slow", slow)
+        .register_with_context("patient", slow)
+        .timeout("slow", Duration::from_millis(50))
+        .default_timeout(Duration::from_secs(5));
+    let client = common::connect(router);
+
+    let started = Instant::now();
+    let err = client.call::<_, Value>("slow", 2_000).unwrap_err();
+    assert_eq!(err.code, RpcError::REQUEST_TIMEOUT);
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    // 在全局超时之内完成的请求正常返回
+    assert_eq!(client.call::<_, u64>("patient", 100).unwrap(), 100);
+    assert_eq!(client.call::<_, u64>("slow", 1).unwrap(), 1);
+}
+
+#[test]
+fn global_timeout_applies_to_every_method() {
+    let mut router = Router::new();
+    router
+        .register_with_context("slow", slow)
+        .default_timeout(Duration::from_millis(50));
+    let client = common::connect(router);
+
+    let err = client.call::<_, Value>("slow", 2_000).unwrap_err();
+    assert_eq!(err.code, RpcError::REQUEST_TIMEOUT);
+}
+
+#[test]
+fn client_deadline_gives_up_and_forgets_the_call() {
+    let mut router = Router::new();
+    router.register_with_context("slow", slow);
+    let client = common::connect(router);
+
+    let started = Instant::now();
+    let err = client
+        .call_with_timeout::<_, Value>("slow", 2_000, Duration::from_millis(50))
+        .unwrap_err();
+    assert_eq!(err.code, RpcError::REQUEST_TIMEOUT);
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert_eq!(client.pending(), 0);
+
+    // 连接仍然可用
+    assert_eq!(
+        client
+            .call_with_timeout::<_, u64>("slow", 1, Duration::from_secs(5))
+            .unwrap(),
+        1
+    );
+}
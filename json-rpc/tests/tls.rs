@@ -0,0 +1,115 @@
+mod common;
+
+use json_rpc::{tls, transport, Client, Framing, Router, RpcError, Server};
+use rcgen::{BasicConstraints, Certificate, CertificateParams, IsCa, KeyPair};
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+/// A throwaway CA that signs certificates for the tests.
+struct Ca {
+    cert: Certificate,
+    key: KeyPair,
+    dir: PathBuf,
+}
+
+impl Ca {
+    fn new(name: &str) -> Ca {
+        let dir =
+            std::env::temp_dir().join(format!("json-rpc-tls-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        let key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::new(Vec::new()).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let cert = params.self_signed(&key).unwrap();
+        fs::write(dir.join("ca.pem"), cert.pem()).unwrap();
+        Ca { cert, key, dir }
+    }
+
+    fn path(&self) -> PathBuf {
+        self.dir.join("ca.pem")
+    }
+
+    /// Issues a certificate for `names` and returns the cert and key paths.
+    fn issue(&self, file: &str, names: &[&str]) -> (PathBuf, PathBuf) {
+        let key = KeyPair::generate().unwrap();
+        let names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+        let cert = CertificateParams::new(names)
+            .unwrap()
+            .signed_by(&key, &self.cert, &self.key)
+            .unwrap();
+        let cert_path = self.dir.join(format!("{}.pem", file));
+        let key_path = self.dir.join(format!("{}-key.pem", file));
+        fs::write(&cert_path, cert.pem()).unwrap();
+        fs::write(&key_path, key.serialize_pem()).unwrap();
+        (cert_path, key_path)
+    }
+}
+
+impl Drop for Ca {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn start_server(ca: &Ca, client_ca: Option<&Path>) -> SocketAddr {
+    let (cert, key) = ca.issue("server", &["localhost", "127.0.0.1"]);
+    let config = tls::server_config(&cert, &key, client_ca).unwrap();
+    let mut router = Router::new();
+    router.register("add", |(a, b): (i64, i64)| Ok::<_, RpcError>(a + b));
+    common::listen(move |listener| {
+        transport::tcp::serve_tls(listener, Server::new(router), Framing::Newline, config)
+    })
+}
+
+#[test]
+fn calls_over_tls() {
+    let ca = Ca::new("plain");
+    let addr = start_server(&ca, None);
+    let config = tls::client_config(&ca.path(), None).unwrap();
+
+    for name in ["localhost", "127.0.0.1"] {
+        let client = Client::connect_tls(addr, name, config.clone(), Framing::Newline).unwrap();
+        assert_eq!(client.call::<_, i64>("add", (2, 3)).unwrap(), 5);
+        client.shutdown().unwrap();
+    }
+}
+
+#[test]
+fn server_certificate_must_be_trusted_and_match() {
+    let ca = Ca::new("trusted");
+    let addr = start_server(&ca, None);
+
+    let other = Ca::new("untrusted");
+    let config = tls::client_config(&other.path(), None).unwrap();
+    assert!(Client::connect_tls(addr, "localhost", config, Framing::Newline).is_err());
+
+    let config = tls::client_config(&ca.path(), None).unwrap();
+    assert!(Client::connect_tls(addr, "example.com", config, Framing::Newline).is_err());
+}
+
+#[test]
+fn mutual_tls_requires_a_client_certificate() {
+    let ca = Ca::new("mutual");
+    let addr = start_server(&ca, Some(&ca.path()));
+
+    let (cert, key) = ca.issue("client", &["client"]);
+    let config = tls::client_config(&ca.path(), Some((&cert, &key))).unwrap();
+    let client = Client::connect_tls(addr, "localhost", config, Framing::Newline).unwrap();
+    assert_eq!(client.call::<_, i64>("add", (20, 22)).unwrap(), 42);
+    client.shutdown().unwrap();
+
+    // 没有证书，或者证书不是这个 CA 签发的：握手失败，调用拿到传输错误
+    let stranger = Ca::new("stranger");
+    let (cert, key) = stranger.issue("client", &["client"]);
+    let configs = [
+        tls::client_config(&ca.path(), None).unwrap(),
+        tls::client_config(&ca.path(), Some((&cert, &key))).unwrap(),
+    ];
+    for config in configs {
+        let rejected = Client::connect_tls(addr, "localhost", config, Framing::Newline)
+            .map_err(RpcError::transport)
+            .and_then(|client| client.call::<_, i64>("add", (1, 1)));
+        assert_eq!(rejected.unwrap_err().code, RpcError::TRANSPORT_ERROR);
+    }
+}
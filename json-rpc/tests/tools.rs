@@ -0,0 +1,126 @@
+mod common;
+
+use json_rpc::tools::{Tools, ALREADY_EXISTS, NOT_FOUND};
+use json_rpc::{Client, Router, RpcError};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+fn connect(todo_file: Option<PathBuf>) -> Client {
+    let mut router = Router::new();
+    Tools::new(todo_file).unwrap().register(&mut router);
+    common::connect(router)
+}
+
+#[test]
+fn calc_and_words() {
+    let client = connect(None);
+    let result: f64 = client
+        .call("calc.eval", json!({"expression": "3 + 4 * 2 / (6 - 5)"}))
+        .unwrap();
+    assert_eq!(result, 11.0);
+    let err = client
+        .call::<_, f64>("calc.eval", json!({"expression": "2 * (3"}))
+        .unwrap_err();
+    assert_eq!(err.code, RpcError::INVALID_PARAMS);
+    for expression in ["(1+2", "1+2)"] {
+        let err = client
+            .call::<_, f64>("calc.eval", json!({"expression": expression}))
+            .unwrap_err();
+        assert_eq!(err.code, RpcError::INVALID_PARAMS);
+        assert_eq!(err.data, Some(json!("mismatched parenthesis")));
+    }
+    for expression in ["1 / 0", "0 / 0"] {
+        let err = client
+            .call::<_, Value>("calc.eval", json!({"expression": expression}))
+            .unwrap_err();
+        assert_eq!(err.code, RpcError::INVALID_PARAMS);
+    }
+
+    let top: Value = client
+        .call("words.top", json!({"text": "b a b c b a", "n": 2}))
+        .unwrap();
+    assert_eq!(
+        top,
+        json!([{"word": "b", "count": 3}, {"word": "a", "count": 2}])
+    );
+}
+
+#[test]
+fn library_catalog() {
+    let client = connect(None);
+    let book =
+        json!({"title": "1984", "author": "George Orwell", "year": 1949, "genre": "fiction"});
+    assert_eq!(client.call::<_, Value>("library.add", &book).unwrap(), book);
+    let err = client.call::<_, Value>("library.add", &book).unwrap_err();
+    assert_eq!(err.code, ALREADY_EXISTS);
+
+    let found: Value = client
+        .call("library.get", json!({"title": "1984"}))
+        .unwrap();
+    assert_eq!(found, book);
+    let fiction: Value = client
+        .call("library.by_genre", json!({"genre": "fiction"}))
+        .unwrap();
+    assert_eq!(fiction, json!([book]));
+
+    client
+        .call::<_, Value>("library.remove", json!({"title": "1984"}))
+        .unwrap();
+    let missing: Value = client
+        .call("library.get", json!({"title": "1984"}))
+        .unwrap();
+    assert!(missing.is_null());
+    let err = client
+        .call::<_, Value>("library.remove", json!({"title": "1984"}))
+        .unwrap_err();
+    assert_eq!(err.code, NOT_FOUND);
+}
+
+#[test]
+fn todo_tasks_are_saved_to_the_file() {
+    let path = std::env::temp_dir().join(format!("json-rpc-todo-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let client = connect(Some(path.clone()));
+    let task: Value = client
+        .call("todo.add", json!({"description": "write tests"}))
+        .unwrap();
+    assert_eq!(task["id"], 1);
+    assert_eq!(task["completed"], false);
+    client
+        .call::<_, Value>("todo.add", json!({"description": "ship"}))
+        .unwrap();
+    let task: Value = client.call("todo.complete", json!({"id": 1})).unwrap();
+    assert_eq!(task["completed"], true);
+    assert!(client
+        .call::<_, bool>("todo.delete", json!({"id": 2}))
+        .unwrap());
+    let err = client
+        .call::<_, Value>("todo.delete", json!({"id": 2}))
+        .unwrap_err();
+    assert_eq!(err.code, NOT_FOUND);
+
+    // 新的服务端从文件读回任务
+    let reopened = connect(Some(path.clone()));
+    let tasks: Value = reopened.call("todo.list", ()).unwrap();
+    assert_eq!(tasks.as_array().unwrap().len(), 1);
+    assert_eq!(tasks[0]["description"], "write tests");
+    assert_eq!(tasks[0]["completed"], true);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn corrupt_todo_file_is_an_error() {
+    let path =
+        std::env::temp_dir().join(format!("json-rpc-todo-corrupt-{}.json", std::process::id()));
+    std::fs::write(&path, "[{\"id\": 1, \"descr").unwrap();
+
+    // 读不懂的文件不能当作空列表，否则下一次保存会把它覆盖掉
+    let err = Tools::new(Some(path.clone())).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "[{\"id\": 1, \"descr"
+    );
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use json_rpc::pubsub::Hub;
+use json_rpc::{Router, Server};
+use serde_json::{json, Value};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::Arc;
+use tungstenite::{connect, Message, WebSocket};
+
+type Socket = WebSocket<tungstenite::stream::MaybeTlsStream<TcpStream>>;
+
+fn start_server() -> (SocketAddr, Arc<Hub>) {
+    let hub = Hub::new();
+    let mut router = Router::new();
+    hub.register(&mut router);
+    let addr = common::serve_websocket(Server::new(router));
+    (addr, hub)
+}
+
+fn open(addr: SocketAddr) -> Socket {
+    connect(format!("ws://{}", addr)).unwrap().0
+}
+
+fn call(ws: &mut Socket, request: Value) -> Value {
+    ws.send(Message::text(request.to_string())).unwrap();
+    receive(ws)
+}
+
+fn receive(ws: &mut Socket) -> Value {
+    loop {
+        if let Message::Text(text) = ws.read().unwrap() {
+            return serde_json::from_str(&text).unwrap();
+        }
+    }
+}
+
+#[test]
+fn subscribers_receive_published_notifications() {
+    let (addr, hub) = start_server();
+    let mut dashboard = open(addr);
+    let mut publisher = open(addr);
+
+    let reply = call(
+        &mut dashboard,
+        json!({"jsonrpc": "2.0", "id": 1, "method": "subscribe", "params": {"topic": "stats"}}),
+    );
+    let subscription = reply["result"].as_u64().unwrap();
+
+    let reply = call(
+        &mut publisher,
+        json!({"jsonrpc": "2.0", "id": 1, "method": "publish", "params": {"topic": "stats", "data": {"load": 3}}}),
+    );
+    assert_eq!(reply["result"], 1);
+
+    let notification = receive(&mut dashboard);
+    assert!(notification.get("id").is_none());
+    assert_eq!(notification["method"], "stats");
+    assert_eq!(notification["params"]["subscription"], subscription);
+    assert_eq!(notification["params"]["result"], json!({"load": 3}));
+
+    // 服务端自己也可以直接推送
+    assert_eq!(hub.publish("stats", &json!("direct")), 1);
+    assert_eq!(receive(&mut dashboard)["params"]["result"], "direct");
+
+    let reply = call(
+        &mut dashboard,
+        json!({"jsonrpc": "2.0", "id": 2, "method": "unsubscribe", "params": {"subscription": subscription}}),
+    );
+    assert_eq!(reply["result"], true);
+    assert_eq!(hub.publish("stats", &json!(null)), 0);
+}
@@ -0,0 +1,120 @@
+//! 图书目录：按书名存取图书，按类别筛选。
+
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct Book {
+    pub title: String,
+    pub author: String,
+    pub year: u32,
+    pub genre: BookGenre,
+}
+
+// impl Clone for Book {
+//     fn clone(&self) -> Self {
+//         Book {
+//             title: self.title.clone(),
+//             author: self.author.clone(),
+//             year: self.year,
+//             genre: self.genre.clone(),
+//         }
+//     }
+// }
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum BookGenre {
+    Fiction,
+    NonFiction,
+    TextBook,
+}
+
+impl Book {
+    pub fn new(title: &str, author: &str, year: u32, genre: BookGenre) -> Self {
+        Book {
+            title: title.to_string(),
+            author: author.to_string(),
+            year,
+            genre,
+        }
+    }
+}
+
+impl fmt::Display for Book {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} by {} {} ({:?})",
+            self.title, self.author, self.year, self.genre
+        )
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LibraryError {
+    BookNotFound,
+    DuplicateBook,
+}
+
+impl fmt::Display for LibraryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LibraryError::BookNotFound => write!(f, "Book not found"),
+            LibraryError::DuplicateBook => write!(f, "Book already exists"),
+        }
+    }
+}
+
+impl std::error::Error for LibraryError {}
+
+pub trait Library<T> {
+    fn add_book(&mut self, book: T) -> Result<(), LibraryError>;
+    fn remove_book(&mut self, title: &str) -> Result<T, LibraryError>;
+    fn get_book(&self, title: &str) -> Option<&T>;
+    fn books_by_genre(&self, genre: &BookGenre) -> Vec<&T>;
+}
+
+pub struct AdvancedLibrary<T> {
+    books: HashMap<String, T>,
+}
+
+// 约束 AdvancedLibrary 的泛型参数 T 要求其实现 Clone
+impl<T: Clone> AdvancedLibrary<T> {
+    pub fn new() -> Self {
+        AdvancedLibrary {
+            books: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Clone> Default for AdvancedLibrary<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Library<Book> for AdvancedLibrary<Book> {
+    fn add_book(&mut self, book: Book) -> Result<(), LibraryError> {
+        if self.books.contains_key(&book.title) {
+            Err(LibraryError::DuplicateBook)
+        } else {
+            self.books.insert(book.title.clone(), book);
+            Ok(())
+        }
+    }
+
+    fn remove_book(&mut self, title: &str) -> Result<Book, LibraryError> {
+        self.books.remove(title).ok_or(LibraryError::BookNotFound)
+    }
+
+    fn get_book(&self, title: &str) -> Option<&Book> {
+        self.books.get(title)
+    }
+
+    fn books_by_genre(&self, genre: &BookGenre) -> Vec<&Book> {
+        self.books
+            .values()
+            .filter(|book| book.genre == *genre)
+            .collect()
+    }
+}
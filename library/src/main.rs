@@ -1,104 +1,4 @@
-use std::collections::HashMap;
-use std::fmt;
-
-#[derive(Debug, Clone)]
-struct Book {
-    title: String,
-    author: String,
-    year: u32,
-    genre: BookGenre,
-}
-
-// impl Clone for Book {
-//     fn clone(&self) -> Self {
-//         Book {
-//             title: self.title.clone(),
-//             author: self.author.clone(),
-//             year: self.year,
-//             genre: self.genre.clone(),
-//         }
-//     }
-// }
-
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
-enum BookGenre {
-    Fiction,
-    NonFiction,
-    TextBook,
-}
-
-impl Book {
-    fn new(title: &str, author: &str, year: u32, genre: BookGenre) -> Self {
-        Book {
-            title: title.to_string(),
-            author: author.to_string(),
-            year,
-            genre,
-        }
-    }
-}
-
-impl fmt::Display for Book {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{} by {} {} ({:?})",
-            self.title, self.author, self.year, self.genre
-        )
-    }
-}
-
-#[derive(Debug)]
-enum LibraryError {
-    BookNotFound,
-    DuplicateBook,
-}
-
-trait Library<T> {
-    fn add_book(&mut self, book: T) -> Result<(), LibraryError>;
-    fn remove_book(&mut self, title: &str) -> Result<T, LibraryError>;
-    fn get_book(&self, title: &str) -> Option<&T>;
-    fn books_by_genre(&self, genre: &BookGenre) -> Vec<&T>;
-}
-
-struct AdvancedLibrary<T> {
-    books: HashMap<String, T>,
-}
-
-// 约束 AdvancedLibrary 的泛型参数 T 要求其实现 Clone
-impl<T: Clone> AdvancedLibrary<T> {
-    fn new() -> Self {
-        AdvancedLibrary {
-            books: HashMap::new(),
-        }
-    }
-}
-
-impl Library<Book> for AdvancedLibrary<Book> {
-    fn add_book(&mut self, book: Book) -> Result<(), LibraryError> {
-        if self.books.contains_key(&book.title) {
-            Err(LibraryError::DuplicateBook)
-        } else {
-            self.books.insert(book.title.clone(), book);
-            Ok(())
-        }
-    }
-
-    fn remove_book(&mut self, title: &str) -> Result<Book, LibraryError> {
-        self.books.remove(title).ok_or(LibraryError::BookNotFound)
-    }
-
-    fn get_book(&self, title: &str) -> Option<&Book> {
-        self.books.get(title)
-    }
-
-    fn books_by_genre(&self, genre: &BookGenre) -> Vec<&Book> {
-        self.books
-            .values()
-            .filter(|book| book.genre == *genre)
-            .collect()
-    }
-}
+use library::{AdvancedLibrary, Book, BookGenre, Library, LibraryError};
 
 fn main() {
     println!("Welcome to the library.");
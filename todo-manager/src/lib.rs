@@ -0,0 +1,110 @@
+//! 任务列表：添加、完成、删除任务，并以 JSON 文件保存。
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Task {
+    pub id: usize,
+    pub description: String,
+    pub completed: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct TaskManager {
+    tasks: Vec<Task>,
+    next_id: usize,
+}
+
+impl Default for TaskManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TaskManager {
+    pub fn new() -> Self {
+        Self {
+            tasks: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Reads tasks from `path`; a missing or blank file is an empty list.
+    /// A file that is not a task list fails with `InvalidData` rather than
+    /// being treated as empty, so the next save can't overwrite it.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(TaskManager::new());
+        }
+        let contents = fs::read_to_string(path)?;
+        if contents.trim().is_empty() {
+            return Ok(TaskManager::new());
+        }
+        let tasks: Vec<Task> = serde_json::from_str(&contents).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), err),
+            )
+        })?;
+        let next_id = tasks.iter().map(|t| t.id).max().unwrap_or(0) + 1;
+        Ok(TaskManager { tasks, next_id })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let serialized = serde_json::to_string_pretty(&self.tasks)?;
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.write_all(serialized.as_bytes())
+    }
+
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
+
+    pub fn tasks_mut(&mut self) -> &mut [Task] {
+        &mut self.tasks
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Task> {
+        self.tasks.iter().find(|t| t.id == id)
+    }
+
+    /// Adds a task and returns its id.
+    pub fn add_task(&mut self, description: String) -> usize {
+        let id = self.next_id;
+        self.tasks.push(Task {
+            id,
+            description,
+            completed: false,
+            created_at: Utc::now(),
+        });
+        self.next_id += 1;
+        id
+    }
+
+    /// Marks task `id` as completed; `false` if there is no such task.
+    pub fn complete_task(&mut self, id: usize) -> bool {
+        match self.tasks.iter_mut().find(|t| t.id == id) {
+            Some(task) => {
+                task.completed = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes task `id`; `false` if there is no such task.
+    pub fn delete_task(&mut self, id: usize) -> bool {
+        let before = self.tasks.len();
+        self.tasks.retain(|t| t.id != id);
+        self.tasks.len() != before
+    }
+}
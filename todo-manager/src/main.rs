@@ -1,4 +1,3 @@
-use chrono::{DateTime, Utc};
 use clap::{Parser, Subcommand};
 use crossterm::{
     cursor,
@@ -6,9 +5,7 @@ use crossterm::{
     execute, queue,
     terminal::{self, disable_raw_mode, enable_raw_mode, ClearType},
 };
-use serde::{Deserialize, Serialize};
 use std::env;
-use std::fs::{File, OpenOptions};
 use std::io::{stdin, stdout, Read, Write};
 use std::path::Path;
 use std::process::Command;
@@ -16,14 +13,7 @@ use std::process::Command;
 use termion::event::Key;
 use termion::input::TermRead;
 use termion::raw::IntoRawMode;
-
-#[derive(Debug, Serialize, Deserialize)]
-struct Task {
-    id: usize,
-    description: String,
-    completed: bool,
-    created_at: DateTime<Utc>,
-}
+use todo_manager::TaskManager;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about=None)]
@@ -40,42 +30,16 @@ enum Commands {
     Delete { id: usize },
 }
 
-struct TaskManager {
-    tasks: Vec<Task>,
-    next_id: usize,
+// 交互界面只在命令行程序里用到，以扩展 trait 的形式挂在 TaskManager 上
+trait Interactive {
+    fn list_tasks(&self);
+    fn interactive_list(&mut self) -> std::io::Result<()>;
+    fn interactive_list_raw(&mut self) -> std::io::Result<()>;
 }
 
-impl TaskManager {
-    fn new() -> Self {
-        Self {
-            tasks: Vec::new(),
-            next_id: 1,
-        }
-    }
-
-    fn add_task(&mut self, description: String) {
-        let task = Task {
-            id: self.next_id,
-            description,
-            completed: false,
-            created_at: Utc::now(),
-        };
-        self.tasks.push(task);
-        println!("Task added with ID: {}", self.next_id);
-        self.next_id += 1;
-    }
-
-    fn complete_task(&mut self, id: usize) {
-        if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
-            task.completed = true;
-            println!("Task {} marked as completed.", id);
-        } else {
-            eprintln!("Task not found.");
-        }
-    }
-
+impl Interactive for TaskManager {
     fn list_tasks(&self) {
-        for task in &self.tasks {
+        for task in self.tasks() {
             let description = if task.completed {
                 // shared borrow occurs here.
                 text_to_strikethrough(&task.description)
@@ -92,26 +56,6 @@ impl TaskManager {
         }
     }
 
-    fn enable_raw_mode() {
-        if cfg!(unix) {
-            Command::new("stty")
-                .arg("raw")
-                .arg("-echo")
-                .spawn()
-                .expect("Failed to enable raw mode");
-        }
-    }
-
-    fn disable_raw_mode() {
-        if cfg!(unix) {
-            Command::new("stty")
-                .arg("-raw")
-                .arg("echo")
-                .spawn()
-                .expect("Failed to disable raw mode");
-        }
-    }
-
     fn interactive_list(&mut self) -> std::io::Result<()> {
         let stdin = std::io::stdin();
         let stdout = std::io::stdout().into_raw_mode()?;
@@ -122,7 +66,7 @@ impl TaskManager {
             print!("{}", termion::cursor::Goto(1, 1));
 
             // 显示任务列表
-            for (index, task) in self.tasks.iter().enumerate() {
+            for (index, task) in self.tasks().iter().enumerate() {
                 if index == selected {
                     print!("> "); // 高亮当前选中项
                 } else {
@@ -139,12 +83,12 @@ impl TaskManager {
             match key? {
                 Key::Char('q') => break,
                 Key::Char(' ') => {
-                    if let Some(task) = self.tasks.get_mut(selected) {
+                    if let Some(task) = self.tasks_mut().get_mut(selected) {
                         task.completed = !task.completed;
                     }
                 }
                 Key::Up if selected > 0 => selected -= 1,
-                Key::Down if selected < self.tasks.len() - 1 => selected += 1,
+                Key::Down if selected < self.tasks().len() - 1 => selected += 1,
                 _ => {}
             }
         }
@@ -159,11 +103,11 @@ impl TaskManager {
     // - `\x1B[u`: 恢复光标位置
     // - `\x1B[{n};1H`: 移动光标到指定行
     fn interactive_list_raw(&mut self) -> std::io::Result<()> {
-        if self.tasks.is_empty() {
+        if self.tasks().is_empty() {
             println!("No tasks.");
             return Ok(());
         }
-        Self::enable_raw_mode();
+        enable_stty_raw_mode();
         let mut selected = 0;
         let mut input = ' ';
         let mut stdout = stdout();
@@ -179,7 +123,7 @@ impl TaskManager {
             stdout.flush()?;
 
             // 显示任务
-            for (index, task) in self.tasks.iter().enumerate() {
+            for (index, task) in self.tasks().iter().enumerate() {
                 // if index == selected {
                 //     // 保存光标位置
                 //     print!("\x1B[s");
@@ -244,7 +188,7 @@ impl TaskManager {
                 }
                 b' ' => {
                     input = ' ';
-                    if let Some(task) = self.tasks.get_mut(selected) {
+                    if let Some(task) = self.tasks_mut().get_mut(selected) {
                         task.completed = !task.completed;
                     }
                 }
@@ -256,7 +200,7 @@ impl TaskManager {
                 }
                 b'j' => {
                     input = 'j';
-                    if selected < self.tasks.len() - 1 {
+                    if selected < self.tasks().len() - 1 {
                         selected += 1
                     }
                 }
@@ -274,7 +218,7 @@ impl TaskManager {
                         }
                         [27, 91, 66] => {
                             // 下箭头
-                            if selected < self.tasks.len() - 1 {
+                            if selected < self.tasks().len() - 1 {
                                 selected += 1;
                             }
                         }
@@ -284,13 +228,28 @@ impl TaskManager {
                 _ => {}
             }
         }
-        Self::disable_raw_mode();
+        disable_stty_raw_mode();
         Ok(())
     }
+}
 
-    fn delete_task(&mut self, id: usize) {
-        self.tasks.retain(|t| t.id != id);
-        println!("Task {} deleted.", id);
+fn enable_stty_raw_mode() {
+    if cfg!(unix) {
+        Command::new("stty")
+            .arg("raw")
+            .arg("-echo")
+            .spawn()
+            .expect("Failed to enable raw mode");
+    }
+}
+
+fn disable_stty_raw_mode() {
+    if cfg!(unix) {
+        Command::new("stty")
+            .arg("-raw")
+            .arg("echo")
+            .spawn()
+            .expect("Failed to disable raw mode");
     }
 }
 
@@ -304,34 +263,6 @@ fn text_to_strikethrough(text: &str) -> String {
 //     text.chars().map(|c| format!("{}\u{0336}", c)).collect()
 // }
 
-fn load_tasks(path_str: &str) -> TaskManager {
-    let path = Path::new(path_str);
-    if !path.exists() {
-        return TaskManager::new();
-    }
-
-    let mut file = File::open(path).expect("Unable to open file.");
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)
-        .expect("Unable to read file.");
-
-    let tasks: Vec<Task> = serde_json::from_str(&contents).unwrap_or_else(|_| Vec::new());
-    let next_id = tasks.iter().map(|t| t.id).max().unwrap_or(0) + 1;
-    TaskManager { tasks, next_id }
-}
-
-fn save_tasks(manager: &TaskManager, path_str: &str) {
-    let serialized = serde_json::to_string_pretty(&manager.tasks).expect("Unable to serialize");
-    let mut file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(path_str)
-        .expect("Unable to open file.");
-    file.write_all(serialized.as_bytes())
-        .expect("Unable to write file");
-}
-
 fn main() {
     println!("Welcome to TODO manager!");
     // CARGO_MANIFEST_DIR 是包的根目录
@@ -341,14 +272,19 @@ fn main() {
     println!("current dir: {:}", file_path.to_str().unwrap());
     let path_str = file_path.to_str().unwrap();
     let cli = Cli::parse();
-    let mut manager = load_tasks(path_str);
+    let mut manager = TaskManager::load(path_str).expect("Unable to read file.");
 
     match &cli.command {
         Some(Commands::Add { description }) => {
-            manager.add_task(description.clone());
+            let id = manager.add_task(description.clone());
+            println!("Task added with ID: {}", id);
         }
         Some(Commands::Complete { id }) => {
-            manager.complete_task(*id);
+            if manager.complete_task(*id) {
+                println!("Task {} marked as completed.", id);
+            } else {
+                eprintln!("Task not found.");
+            }
         }
         Some(Commands::List) => {
             // manager.list_tasks();
@@ -358,10 +294,11 @@ fn main() {
         }
         Some(Commands::Delete { id }) => {
             manager.delete_task(*id);
+            println!("Task {} deleted.", id);
         }
         None => {
             println!("No command specified. Use --help for usage infomation.")
         }
     }
-    save_tasks(&manager, path_str);
+    manager.save(path_str).expect("Unable to write file");
 }
@@ -0,0 +1,133 @@
+//! 统计单词出现的频率，并找出最常见的 N 个单词。
+//!
+//! 分词遵循 Unicode 单词边界规则（UAX #29）：标点不算单词的一部分，
+//! `don't` 这样的缩写保持完整，中日文的汉字逐字计数。
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Unicode normalization applied to the text before it is split into words.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Normalization {
+    #[default]
+    None,
+    /// Canonical composition: `e` + combining acute and `é` become the same word.
+    Nfc,
+    /// Compatibility composition: additionally folds ligatures and full-width
+    /// forms, e.g. `ﬁle` and `ｆｉｌｅ` become `file`.
+    Nfkc,
+}
+
+/// Which words count as the same word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// Count `Hello` and `hello` as one word (on by default).
+    pub fold_case: bool,
+    pub normalization: Normalization,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            fold_case: true,
+            normalization: Normalization::None,
+        }
+    }
+}
+
+/// Splits `text` into words, dropping punctuation and whitespace.
+pub fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.unicode_words()
+}
+
+/// Word counts keyed by the word after case folding and normalization.
+#[derive(Debug, Default, Clone)]
+pub struct WordCounts {
+    counts: HashMap<String, u32>,
+    options: Options,
+}
+
+impl WordCounts {
+    pub fn new() -> Self {
+        WordCounts::default()
+    }
+
+    pub fn with_options(options: Options) -> Self {
+        WordCounts {
+            counts: HashMap::new(),
+            options,
+        }
+    }
+
+    pub fn options(&self) -> Options {
+        self.options
+    }
+
+    pub fn add_text(&mut self, text: &str) {
+        let text = self.normalize(text);
+        for word in words(&text) {
+            let word = self.key(word);
+            *self.counts.entry(word).or_insert(0) += 1;
+        }
+    }
+
+    fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self.options.normalization {
+            Normalization::None => Cow::Borrowed(text),
+            Normalization::Nfc => Cow::Owned(text.nfc().collect()),
+            Normalization::Nfkc => Cow::Owned(text.nfkc().collect()),
+        }
+    }
+
+    /// The form a word is counted under.
+    fn key(&self, word: &str) -> String {
+        // 弯引号和直引号写出的 `don’t`/`don't` 是同一个词
+        let word = word.replace('\u{2019}', "'");
+        if !self.options.fold_case {
+            return word;
+        }
+        // `to_lowercase` 之外再补上完整大小写折叠中最常见的两处差异
+        word.to_lowercase().replace('ß', "ss").replace('ς', "σ")
+    }
+
+    pub fn add_reader<R: BufRead>(&mut self, reader: R) -> io::Result<()> {
+        for line in reader.lines() {
+            // 每个迭代项都是 `Result`
+            self.add_text(&line?);
+        }
+        Ok(())
+    }
+
+    /// Keeps only the words for which `keep(word, count)` returns `true`.
+    pub fn retain<F: FnMut(&str, u32) -> bool>(&mut self, mut keep: F) {
+        self.counts.retain(|word, count| keep(word, *count));
+    }
+
+    /// How often `word` occurred, compared the same way words are counted.
+    pub fn get(&self, word: &str) -> u32 {
+        let word = self.key(&self.normalize(word));
+        self.counts.get(&word).copied().unwrap_or(0)
+    }
+
+    /// The `n` most frequent words, most frequent first; ties are broken
+    /// alphabetically so the output is stable.
+    pub fn top(&self, n: usize) -> Vec<(&str, u32)> {
+        let mut counts: Vec<_> = self
+            .counts
+            .iter()
+            .map(|(word, count)| (word.as_str(), *count))
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        counts.truncate(n);
+        counts
+    }
+}
+
+pub fn count_words(text: &str) -> WordCounts {
+    let mut counts = WordCounts::new();
+    counts.add_text(text);
+    counts
+}
@@ -1,45 +1,127 @@
-use std::collections::HashMap;
-use std::env;
+//! 目标：创建一个程序，读取文本文件，统计单词出现的频率，并输出最常见的 N 个单词。
+//!
+//! Usage: word-counter [OPTIONS] [FILES]...
+
+use clap::{Parser, ValueEnum};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-/// 目标：创建一个程序，读取文本文件，统计单词出现的频率，并输出最常见的 N 个单词。
-///
-/// Usage: {} <filename> <top_n>
-
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 3 {
-        println!("Usage: {} <filename> <top_n>", args[0]);
-        std::process::exit(1);
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use std::process;
+use word_counter::{Normalization, Options, WordCounts};
+
+/// Exit code when some input could not be read.
+const EXIT_INPUT: i32 = 1;
+/// Exit code for bad arguments (same as clap).
+const EXIT_USAGE: i32 = 2;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Normalize {
+    None,
+    Nfc,
+    Nfkc,
+}
+
+#[derive(Parser)]
+#[command(
+    about = "Counts word frequencies and prints the most common words",
+    after_help = "Exit status: 0 on success, 1 if an input could not be read, \
+                  2 for usage errors."
+)]
+struct Cli {
+    /// Files or glob patterns such as 'docs/*.txt'; `-` or no files reads stdin
+    #[arg(value_name = "FILES")]
+    inputs: Vec<String>,
+
+    /// Number of words to print; 0 prints all of them
+    #[arg(long, short = 'n', value_name = "N", default_value_t = 10)]
+    top: usize,
+
+    /// Only print words that occur at least this many times
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    min_count: u32,
+
+    /// Only print words with at least this many characters
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    min_length: usize,
+
+    /// Count `Hello` and `hello` as the same word (the default)
+    #[arg(long, short = 'i', overrides_with = "case_sensitive")]
+    ignore_case: bool,
+
+    /// Count `Hello` and `hello` as different words
+    #[arg(long, short = 's', overrides_with = "ignore_case")]
+    case_sensitive: bool,
+
+    /// Unicode normalization applied before counting
+    #[arg(long, value_enum, default_value_t = Normalize::None)]
+    normalize: Normalize,
+}
+
+/// Expands a glob pattern; anything that is not a pattern is used as is.
+fn expand(input: &str) -> Result<Vec<PathBuf>, glob::PatternError> {
+    if !input.contains(['*', '?', '[']) {
+        return Ok(vec![PathBuf::from(input)]);
     }
-    let filename = &args[1];
-    let top_n: usize = args[2].parse()?;
+    // 匹配过程中读不了的目录直接跳过
+    Ok(glob::glob(input)?.filter_map(Result::ok).collect())
+}
 
-    let file = File::open(filename)?;
-    let reader = BufReader::new(file);
+fn add_input(counts: &mut WordCounts, path: &Path) -> io::Result<()> {
+    if path.as_os_str() == "-" {
+        return counts.add_reader(io::stdin().lock());
+    }
+    counts.add_reader(BufReader::new(File::open(path)?))
+}
 
-    let mut word_counts: HashMap<String, u32> = HashMap::new();
+fn main() {
+    let cli = Cli::parse();
+    let inputs = if cli.inputs.is_empty() {
+        vec!["-".to_string()]
+    } else {
+        cli.inputs.clone()
+    };
 
-    for line in reader.lines() {
-        // 每个迭代项都是 `Result`
-        let line = line?;
-        for word in line.split_whitespace() {
-            let word = word.to_lowercase();
-            *word_counts.entry(word).or_insert(0) += 1;
+    // 先检查所有模式，语法错误时什么都不读
+    let mut expanded = Vec::new();
+    for input in &inputs {
+        match expand(input) {
+            Ok(paths) => expanded.push((input, paths)),
+            Err(err) => {
+                eprintln!("word-counter: {}: {}", input, err);
+                process::exit(EXIT_USAGE);
+            }
         }
     }
 
-    let mut word_counts: Vec<_> = word_counts.into_iter().collect();
-    word_counts.sort_by(|a, b| b.1.cmp(&a.1));
-
-    println!("Top {} words: ", top_n);
-    // for (word, count) in word_counts.iter().take(top_n) {
-    //     println!("{}: {}", word, count);
-    // }
+    let mut counts = WordCounts::with_options(Options {
+        fold_case: !cli.case_sensitive,
+        normalization: match cli.normalize {
+            Normalize::None => Normalization::None,
+            Normalize::Nfc => Normalization::Nfc,
+            Normalize::Nfkc => Normalization::Nfkc,
+        },
+    });
+    // 读不了的输入先报告并跳过，其余的照常统计，最后以非零状态退出
+    let mut failed = false;
+    for (input, paths) in expanded {
+        if paths.is_empty() {
+            eprintln!("word-counter: {}: no files match", input);
+            failed = true;
+        }
+        for path in paths {
+            if let Err(err) = add_input(&mut counts, &path) {
+                eprintln!("word-counter: {}: {}", path.display(), err);
+                failed = true;
+            }
+        }
+    }
 
-    // optimized version:
-    for (word, count) in &word_counts[..top_n.min(word_counts.len())] {
+    counts.retain(|word, count| count >= cli.min_count && word.chars().count() >= cli.min_length);
+    let n = if cli.top == 0 { usize::MAX } else { cli.top };
+    for (word, count) in counts.top(n) {
         println!("{}: {}", word, count);
     }
-    Ok(())
+    if failed {
+        process::exit(EXIT_INPUT);
+    }
 }
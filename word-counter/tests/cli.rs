@@ -0,0 +1,96 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_word-counter"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to start word-counter");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+/// A directory with `a.txt` and `b.txt`, removed when dropped.
+struct Fixture(PathBuf);
+
+impl Fixture {
+    fn new(name: &str) -> Fixture {
+        let dir =
+            std::env::temp_dir().join(format!("word-counter-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.txt"), "apple banana apple\n").unwrap();
+        fs::write(dir.join("b.txt"), "Apple cherry, banana!\n").unwrap();
+        Fixture(dir)
+    }
+
+    fn path(&self, name: &str) -> String {
+        self.0.join(name).display().to_string()
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn counts_files_globs_and_stdin_together() {
+    let fixture = Fixture::new("inputs");
+    let output = run(&[&fixture.path("*.txt"), "-"], "cherry pie");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "apple: 3\nbanana: 2\ncherry: 2\npie: 1\n");
+}
+
+#[test]
+fn filters_and_case_sensitivity() {
+    let text = "The the THE a a an an an cat";
+    let output = run(&["--top", "2", "--min-length", "2"], text);
+    assert_eq!(stdout(&output), "an: 3\nthe: 3\n");
+
+    let output = run(&["--min-count", "2", "--case-sensitive"], text);
+    assert_eq!(stdout(&output), "an: 3\na: 2\n");
+
+    // 后出现的开关生效
+    let output = run(&["-s", "--ignore-case", "-n", "1"], text);
+    assert_eq!(stdout(&output), "an: 3\n");
+}
+
+#[test]
+fn exit_codes() {
+    let fixture = Fixture::new("exit");
+    let missing = fixture.path("missing.txt");
+    let output = run(&[&fixture.path("a.txt"), &missing], "");
+    assert_eq!(output.status.code(), Some(1));
+    // 其余输入照常统计
+    assert_eq!(stdout(&output), "apple: 2\nbanana: 1\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("missing.txt"));
+
+    let output = run(&[&fixture.path("*.md")], "");
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = run(&["[", &fixture.path("a.txt")], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+
+    let output = run(&["--top", "many"], "");
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = run(&["--help"], "");
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("--min-count"));
+}
@@ -0,0 +1,66 @@
+use word_counter::{count_words, words, Normalization, Options, WordCounts};
+
+#[test]
+fn punctuation_and_case_do_not_make_new_words() {
+    let counts = count_words("Hello, hello. HELLO! \"hello\" (world)");
+    assert_eq!(counts.get("hello"), 4);
+    assert_eq!(counts.get("Hello"), 4);
+    assert_eq!(counts.top(2), [("hello", 4), ("world", 1)]);
+}
+
+#[test]
+fn apostrophes_stay_inside_words() {
+    let counts = count_words("Don't stop; don’t 'quote' me");
+    assert_eq!(counts.get("don't"), 2);
+    assert_eq!(counts.get("quote"), 1);
+    assert_eq!(
+        words("it's the dogs' bone").collect::<Vec<_>>(),
+        ["it's", "the", "dogs", "bone"]
+    );
+}
+
+#[test]
+fn cjk_and_numbers_are_split_on_word_boundaries() {
+    let counts = count_words("我爱北京，北京很大。 3.14 and 3.14");
+    assert_eq!(counts.get("北"), 2);
+    assert_eq!(counts.get("京"), 2);
+    assert_eq!(counts.get("3.14"), 2);
+}
+
+#[test]
+fn case_folding_is_optional() {
+    let text = "Straße STRASSE Ὀδυσσεύς ὈΔΥΣΣΕΎΣ";
+    let folded = count_words(text);
+    assert_eq!(folded.get("strasse"), 2);
+    assert_eq!(folded.get("ὀδυσσεύς"), 2);
+
+    let mut exact = WordCounts::with_options(Options {
+        fold_case: false,
+        ..Options::default()
+    });
+    exact.add_text(text);
+    assert_eq!(exact.get("Straße"), 1);
+    assert_eq!(exact.get("strasse"), 0);
+}
+
+#[test]
+fn normalization_merges_equivalent_spellings() {
+    // 预组合的 é 和 e + 组合重音，以及连字和全角字母
+    let text = "caf\u{e9} cafe\u{301} \u{fb01}le ｆｉｌｅ file";
+    assert_eq!(count_words(text).get("file"), 1);
+
+    let mut nfc = WordCounts::with_options(Options {
+        normalization: Normalization::Nfc,
+        ..Options::default()
+    });
+    nfc.add_text(text);
+    assert_eq!(nfc.get("café"), 2);
+    assert_eq!(nfc.get("file"), 1);
+
+    let mut nfkc = WordCounts::with_options(Options {
+        normalization: Normalization::Nfkc,
+        ..Options::default()
+    });
+    nfkc.add_text(text);
+    assert_eq!(nfkc.get("file"), 3);
+}